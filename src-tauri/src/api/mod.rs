@@ -1,16 +1,25 @@
 use axum::{
-    extract::{Path, State},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
     http::StatusCode,
-    response::Json,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json, Response,
+    },
     routing::{get, post},
     Router,
 };
+use std::convert::Infallible;
 use std::net::SocketAddr;
 use std::sync::mpsc::{self, Receiver};
-use tokio::sync::oneshot;
+use tokio::sync::{broadcast, oneshot};
 use tokio::task::JoinHandle;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
 use tower_http::cors::{Any, CorsLayer};
 
+use crate::audio::AudioAnalysisUpdate;
 use crate::engine::{EngineCommand, EngineRequest};
 use crate::store::{EngineState, Scene}; // <-- Import EngineState
 use crate::types::{Device, Virtual}; // <-- Import Device
@@ -23,12 +32,14 @@ pub enum ApiCommand {
 struct ApiState {
     engine_command_tx: mpsc::Sender<EngineCommand>,
     engine_state_tx: mpsc::Sender<EngineRequest>,
+    analysis_tx: broadcast::Sender<AudioAnalysisUpdate>,
 }
 
 pub async fn api_server_manager(
     api_command_rx: Receiver<ApiCommand>,
     engine_command_tx: mpsc::Sender<EngineCommand>,
     engine_state_tx: mpsc::Sender<EngineRequest>,
+    analysis_tx: broadcast::Sender<AudioAnalysisUpdate>,
     initial_port: u16,
 ) {
     #[allow(unused)]
@@ -38,6 +49,7 @@ pub async fn api_server_manager(
         let state = ApiState {
             engine_command_tx: engine_command_tx.clone(),
             engine_state_tx: engine_state_tx.clone(),
+            analysis_tx: analysis_tx.clone(),
         };
 
         tokio::spawn(async move {
@@ -57,6 +69,8 @@ pub async fn api_server_manager(
                 )
                 .route("/virtuals", get(get_virtuals_handler))
                 .route("/virtuals/:id/effects/stop", post(stop_effect_handler))
+                .route("/audio/analysis/ws", get(analysis_ws_handler))
+                .route("/audio/analysis/sse", get(analysis_sse_handler))
                 .with_state(state)
                 .layer(cors);
             let addr = SocketAddr::from(([0, 0, 0, 0], port));
@@ -124,18 +138,18 @@ async fn request_engine_state<T: Send + 'static>(
 async fn get_full_state_handler(
     State(state): State<ApiState>,
 ) -> Result<Json<EngineState>, StatusCode> {
-    request_engine_state(&state.engine_state_tx, |tx| EngineRequest::GetFullState(tx)).await
+    request_engine_state(&state.engine_state_tx, EngineRequest::GetFullState).await
 }
 
 async fn get_devices_handler(
     State(state): State<ApiState>,
 ) -> Result<Json<Vec<Device>>, StatusCode> {
-    request_engine_state(&state.engine_state_tx, |tx| EngineRequest::GetDevices(tx)).await
+    request_engine_state(&state.engine_state_tx, EngineRequest::GetDevices).await
 }
 // --- END: NEW HANDLERS ---
 
 async fn get_scenes_handler(State(state): State<ApiState>) -> Result<Json<Vec<Scene>>, StatusCode> {
-    request_engine_state(&state.engine_state_tx, |tx| EngineRequest::GetScenes(tx)).await
+    request_engine_state(&state.engine_state_tx, EngineRequest::GetScenes).await
 }
 async fn activate_scene_handler(
     State(state): State<ApiState>,
@@ -151,7 +165,7 @@ async fn activate_scene_handler(
 async fn get_virtuals_handler(
     State(state): State<ApiState>,
 ) -> Result<Json<Vec<Virtual>>, StatusCode> {
-    request_engine_state(&state.engine_state_tx, |tx| EngineRequest::GetVirtuals(tx)).await
+    request_engine_state(&state.engine_state_tx, EngineRequest::GetVirtuals).await
 }
 async fn stop_effect_handler(
     State(state): State<ApiState>,
@@ -164,3 +178,42 @@ async fn stop_effect_handler(
         StatusCode::INTERNAL_SERVER_ERROR
     }
 }
+
+// Live `audio-analysis` updates, as published by `audio::run_analysis_publisher`.
+async fn analysis_ws_handler(ws: WebSocketUpgrade, State(state): State<ApiState>) -> Response {
+    let analysis_rx = state.analysis_tx.subscribe();
+    ws.on_upgrade(move |socket| stream_analysis_ws(socket, analysis_rx))
+}
+async fn stream_analysis_ws(
+    mut socket: WebSocket,
+    mut analysis_rx: broadcast::Receiver<AudioAnalysisUpdate>,
+) {
+    loop {
+        match analysis_rx.recv().await {
+            Ok(update) => {
+                let Ok(json) = serde_json::to_string(&update) else {
+                    continue;
+                };
+                if socket.send(Message::Text(json)).await.is_err() {
+                    break;
+                }
+            }
+            // A slow client just skips the updates it missed.
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+async fn analysis_sse_handler(
+    State(state): State<ApiState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(state.analysis_tx.subscribe()).filter_map(|update| {
+        let update = update.ok()?;
+        Event::default()
+            .event("audio-analysis")
+            .json_data(update)
+            .ok()
+            .map(Ok)
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+// Per-band peaks never drop below this fraction of the global peak, so empty
+// bands are not blown up to full brightness by their own noise.
+const PER_BAND_FLOOR: f32 = 0.1;
+const MIN_PEAK: f32 = 1e-4;
+
+/// How band energies are normalized into the 0..1 range effects work with.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, Default, PartialEq)]
+pub enum AgcStrategy {
+    /// Divide every band by one adaptive peak over all bands.
+    #[default]
+    GlobalPeak,
+    /// Track an adaptive peak per band, so loud bass doesn't wash out the highs.
+    PerBand,
+    /// No adaptation; `gain` times the level relative to full scale.
+    FixedGain { gain: f32 },
+    /// Level in dB relative to full scale, mapped linearly from `floor_db` (0) to 0 dB (1).
+    Logarithmic { floor_db: f32 },
+}
+
+/// Automatic gain control over the smoothed filterbank output.
+pub struct Agc {
+    full_scale: f32,
+    peak_energy: f32,
+    band_peaks: Vec<f32>,
+    gain: f32,
+}
+
+fn follow(peak: f32, value: f32, attack: f32, decay: f32) -> f32 {
+    let rate = if value > peak { attack } else { decay };
+    (peak * (1.0 - rate) + value * rate).max(MIN_PEAK)
+}
+
+impl Agc {
+    /// `full_scale` is the band energy a full-scale sine produces, used by the
+    /// fixed and logarithmic strategies.
+    pub fn new(num_bands: usize, full_scale: f32) -> Self {
+        Self {
+            full_scale: full_scale.max(MIN_PEAK),
+            peak_energy: 1.0,
+            band_peaks: vec![1.0; num_bands],
+            gain: 1.0,
+        }
+    }
+
+    /// Gain applied to the last frame (the average over bands for `PerBand`).
+    /// For `Logarithmic` this is the gain that maps full scale to 0 dB.
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Normalizes one frame of band energies. With `hold` set the adaptive peaks
+    /// are frozen, e.g. while the noise gate is closed.
+    pub fn process(
+        &mut self,
+        bands: &[f32],
+        strategy: &AgcStrategy,
+        attack: f32,
+        decay: f32,
+        hold: bool,
+    ) -> Vec<f32> {
+        if self.band_peaks.len() != bands.len() {
+            self.band_peaks = vec![self.peak_energy; bands.len()];
+        }
+        let frame_max = bands.iter().fold(0.0f32, |max, &v| max.max(v));
+        if !hold {
+            self.peak_energy = follow(self.peak_energy, frame_max, attack, decay);
+            if *strategy == AgcStrategy::PerBand {
+                for (peak, &value) in self.band_peaks.iter_mut().zip(bands) {
+                    *peak = follow(*peak, value, attack, decay);
+                }
+            }
+        }
+
+        match strategy {
+            AgcStrategy::GlobalPeak => {
+                self.gain = 1.0 / self.peak_energy;
+                bands.iter().map(|&v| (v * self.gain).min(1.0)).collect()
+            }
+            AgcStrategy::PerBand => {
+                let floor = self.peak_energy * PER_BAND_FLOOR;
+                let mut gain_sum = 0.0;
+                let normalized = bands
+                    .iter()
+                    .zip(&self.band_peaks)
+                    .map(|(&v, &peak)| {
+                        let gain = 1.0 / peak.max(floor);
+                        gain_sum += gain;
+                        (v * gain).min(1.0)
+                    })
+                    .collect();
+                self.gain = gain_sum / bands.len().max(1) as f32;
+                normalized
+            }
+            AgcStrategy::FixedGain { gain } => {
+                self.gain = gain / self.full_scale;
+                bands
+                    .iter()
+                    .map(|&v| (v * self.gain).clamp(0.0, 1.0))
+                    .collect()
+            }
+            AgcStrategy::Logarithmic { floor_db } => {
+                self.gain = 1.0 / self.full_scale;
+                let floor_db = floor_db.min(-1.0);
+                bands
+                    .iter()
+                    .map(|&v| {
+                        let db = super::amplitude_to_dbfs(v * self.gain);
+                        ((db - floor_db) / -floor_db).clamp(0.0, 1.0)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-3, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    fn settle(agc: &mut Agc, bands: &[f32], strategy: &AgcStrategy) -> Vec<f32> {
+        let mut out = Vec::new();
+        for _ in 0..200 {
+            out = agc.process(bands, strategy, 0.5, 0.5, false);
+        }
+        out
+    }
+
+    #[test]
+    fn global_peak_keeps_ratios_between_bands() {
+        let mut agc = Agc::new(3, 1.0);
+        let out = settle(&mut agc, &[4.0, 2.0, 1.0], &AgcStrategy::GlobalPeak);
+        approx(&out, &[1.0, 0.5, 0.25]);
+        assert!((agc.gain() - 0.25).abs() < 1e-3);
+    }
+
+    #[test]
+    fn global_peak_adapts_to_quieter_input() {
+        let mut agc = Agc::new(2, 1.0);
+        settle(&mut agc, &[10.0, 5.0], &AgcStrategy::GlobalPeak);
+        let first = agc.process(&[1.0, 0.5], &AgcStrategy::GlobalPeak, 0.5, 0.1, false);
+        assert!(first[0] < 0.2);
+        let out = settle(&mut agc, &[1.0, 0.5], &AgcStrategy::GlobalPeak);
+        approx(&out, &[1.0, 0.5]);
+    }
+
+    #[test]
+    fn global_peak_lets_bass_wash_out_highs() {
+        let mut agc = Agc::new(2, 1.0);
+        let out = settle(&mut agc, &[10.0, 2.0], &AgcStrategy::GlobalPeak);
+        approx(&out, &[1.0, 0.2]);
+    }
+
+    #[test]
+    fn per_band_lifts_quiet_bands() {
+        let mut agc = Agc::new(2, 1.0);
+        let out = settle(&mut agc, &[10.0, 2.0], &AgcStrategy::PerBand);
+        approx(&out, &[1.0, 1.0]);
+        assert!((agc.gain() - (0.1 + 0.5) / 2.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn per_band_floor_keeps_empty_bands_dark() {
+        let mut agc = Agc::new(2, 1.0);
+        let out = settle(&mut agc, &[10.0, 0.01], &AgcStrategy::PerBand);
+        // The empty band is measured against 10% of the global peak, not its own noise.
+        approx(&out, &[1.0, 0.01]);
+    }
+
+    #[test]
+    fn fixed_gain_is_linear_and_clips() {
+        let strategy = AgcStrategy::FixedGain { gain: 2.0 };
+        let mut agc = Agc::new(3, 4.0);
+        let out = agc.process(&[1.0, 1.5, 3.0], &strategy, 0.5, 0.5, false);
+        approx(&out, &[0.5, 0.75, 1.0]);
+        // Loud frames don't change how later frames are scaled.
+        settle(&mut agc, &[100.0, 100.0, 100.0], &strategy);
+        let out = agc.process(&[1.0, 1.5, 3.0], &strategy, 0.5, 0.5, false);
+        approx(&out, &[0.5, 0.75, 1.0]);
+        assert!((agc.gain() - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn logarithmic_maps_floor_to_zero_and_full_scale_to_one() {
+        let strategy = AgcStrategy::Logarithmic { floor_db: -60.0 };
+        let mut agc = Agc::new(5, 2.0);
+        let out = agc.process(
+            &[2.0, 2.0 * 10f32.powf(-30.0 / 20.0), 2e-3, 2e-5, 0.0],
+            &strategy,
+            0.5,
+            0.5,
+            false,
+        );
+        approx(&out, &[1.0, 0.5, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn logarithmic_compresses_dynamics() {
+        let strategy = AgcStrategy::Logarithmic { floor_db: -40.0 };
+        let mut agc = Agc::new(2, 1.0);
+        // A band 20 dB (10x) quieter is still half as bright, not a tenth.
+        let out = agc.process(&[1.0, 0.1], &strategy, 0.5, 0.5, false);
+        approx(&out, &[1.0, 0.5]);
+    }
+
+    #[test]
+    fn hold_freezes_adaptive_peaks() {
+        for strategy in [AgcStrategy::GlobalPeak, AgcStrategy::PerBand] {
+            let mut agc = Agc::new(2, 1.0);
+            settle(&mut agc, &[4.0, 4.0], &strategy);
+            for _ in 0..100 {
+                agc.process(&[0.01, 0.01], &strategy, 0.5, 0.5, true);
+            }
+            let out = agc.process(&[2.0, 2.0], &strategy, 0.5, 0.5, true);
+            approx(&out, &[0.5, 0.5]);
+        }
+    }
+}
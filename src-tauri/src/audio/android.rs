@@ -1,18 +1,15 @@
 use super::{
-    AudioAnalysisData, AudioCommand, AudioDevice, AudioDevicesInfo, DspSettings, SharedDspSettings,
+    AnalysisSink, AudioAnalysisData, AudioCommand, AudioDevice, AudioDevicesInfo, DspSettings,
+    SharedDspSettings,
 };
 use crate::audio::shared_processing::build_and_play_stream_shared;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, Stream};
-use dasp::{interpolate::linear::Linear, signal, Signal};
 use dasp_sample::Sample;
 use jni::objects::{JByteArray, JClass};
 use jni::sys::jint;
 use jni::JNIEnv;
 use once_cell::sync::Lazy;
-use rustfft::num_complex::Complex;
-use rustfft::FftPlanner;
-use std::f32::consts::PI;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc, Mutex};
 use tauri::State;
@@ -46,7 +43,9 @@ pub fn run_android_capture(
                     );
 
                     if let Some(stream) = current_cpal_stream.take() {
-                        stream.pause().expect("Failed to pause CPAL stream");
+                        if let Err(e) = stream.pause() {
+                            eprintln!("[AUDIO] Failed to pause CPAL stream: {}", e);
+                        }
                         drop(stream);
                         println!("[AUDIO] Stopped existing CPAL stream.");
                     }
@@ -60,13 +59,16 @@ pub fn run_android_capture(
 
                         if let Some(device) = find_device(&host, &device_name) {
                             if let Ok(config) = device.default_input_config() {
-                                let stream = build_and_play_stream_shared(
+                                match build_and_play_stream_shared(
                                     device,
                                     config,
                                     audio_data.clone(),
                                     dsp_settings.clone(),
-                                );
-                                current_cpal_stream = Some(stream);
+                                    None,
+                                ) {
+                                    Ok(stream) => current_cpal_stream = Some(stream),
+                                    Err(e) => eprintln!("[AUDIO] {}", e),
+                                }
                             } else {
                                 eprintln!(
                                     "[AUDIO] Could not get default input config for {}",
@@ -89,74 +91,26 @@ pub fn run_android_capture(
                 AudioCommand::RestartStream => {
                     println!("[AUDIO] Android received RestartStream command (no-op, frontend should re-select device).");
                 }
+                AudioCommand::PlayFile { .. }
+                | AudioCommand::SeekFile(_)
+                | AudioCommand::SetFileLooping(_) => {
+                    eprintln!("[AUDIO] File playback is not supported on Android.");
+                }
+                AudioCommand::ReplayAnalysis { .. } => {
+                    eprintln!("[AUDIO] Analysis replay is not supported on Android.");
+                }
+                AudioCommand::SetInput { name, .. } | AudioCommand::RemoveInput(name) => {
+                    eprintln!(
+                        "[AUDIO] Additional audio inputs are not supported on Android ({}).",
+                        name
+                    );
+                }
             }
         }
         std::thread::sleep(std::time::Duration::from_millis(10));
     }
 }
 
-struct AudioProcessingState {
-    fft_size: usize,
-    num_bands: usize,
-    audio_samples: Vec<f32>,
-    fft_buffer: Vec<Complex<f32>>,
-    window: Vec<f32>,
-    fft_plan: Arc<dyn rustfft::Fft<f32>>,
-    filterbank: Vec<Vec<(usize, f32)>>,
-    smoothed_melbanks: Vec<f32>,
-    peak_energy: f32,
-}
-
-impl Default for AudioProcessingState {
-    fn default() -> Self {
-        Self {
-            fft_size: 0,
-            num_bands: 0,
-            audio_samples: Vec::new(),
-            fft_buffer: Vec::new(),
-            window: Vec::new(),
-            fft_plan: FftPlanner::new().plan_fft_forward(0),
-            filterbank: Vec::new(),
-            smoothed_melbanks: Vec::new(),
-            peak_energy: 1.0,
-        }
-    }
-}
-
-impl AudioProcessingState {
-    fn initialize(
-        &mut self,
-        fft_size: usize,
-        num_bands: usize,
-        sample_rate: u32,
-        settings: &DspSettings,
-    ) {
-        println!(
-            "[ANDROID JNI] Initializing DSP state. FFT Size: {}, Bands: {}",
-            fft_size, num_bands
-        );
-        self.fft_size = fft_size;
-        self.num_bands = num_bands;
-        self.audio_samples.clear();
-        self.audio_samples.reserve(fft_size * 2);
-        self.fft_buffer = vec![Complex::new(0.0, 0.0); fft_size];
-        self.window = (0..fft_size)
-            .map(|i| 0.5 * (1.0 - (2.0 * PI * i as f32 / (fft_size - 1) as f32).cos()))
-            .collect();
-        self.fft_plan = FftPlanner::new().plan_fft_forward(fft_size);
-        self.smoothed_melbanks = vec![0.0; num_bands];
-        self.peak_energy = 1.0;
-        self.filterbank = crate::utils::dsp::generate_filterbank(
-            fft_size / 2,
-            sample_rate,
-            num_bands,
-            settings.min_freq,
-            settings.max_freq,
-            &settings.filterbank_type,
-        );
-    }
-}
-
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system" fn Java_com_blade_ledfxrust_AudioVisualizer_onPcmDataCapture(
@@ -169,115 +123,20 @@ pub extern "system" fn Java_com_blade_ledfxrust_AudioVisualizer_onPcmDataCapture
         return;
     }
 
-    static PROCESSING_STATE: Lazy<Mutex<AudioProcessingState>> = Lazy::new(Default::default);
-    let mut state = PROCESSING_STATE.lock().unwrap();
-    let settings = SHARED_DSP_SETTINGS.0.lock().unwrap();
-
-    let fft_size = settings.fft_size as usize;
-    if state.fft_size != fft_size {
-        state.initialize(
-            fft_size,
-            settings.num_bands as usize,
-            sampling_rate as u32,
-            &settings,
-        );
-    }
+    static SINK: Lazy<Mutex<AnalysisSink>> = Lazy::new(|| {
+        Mutex::new(AnalysisSink::new(
+            SHARED_AUDIO_DATA.clone(),
+            SHARED_DSP_SETTINGS.0.clone(),
+        ))
+    });
 
     let pcm_bytes = env.convert_byte_array(pcm_data).unwrap();
-    let samples_i16: Vec<i16> = pcm_bytes
+    let samples: Vec<f32> = pcm_bytes
         .chunks_exact(2)
-        .map(|a| i16::from_le_bytes([a[0], a[1]]))
+        .map(|a| i16::from_le_bytes([a[0], a[1]]).to_sample::<f32>())
         .collect();
 
-    let source_sample_rate = sampling_rate as u32;
-    let target_sample_rate = settings.sample_rate;
-
-    let mono_samples_iterator: Box<dyn Iterator<Item = f32>> =
-        if let Some(target_rate) = target_sample_rate {
-            if target_rate == source_sample_rate {
-                Box::new(samples_i16.into_iter().map(|s| s.to_sample::<f32>()))
-            } else {
-                println!(
-                    "[ANDROID JNI] Resampling from {}Hz to {}Hz",
-                    source_sample_rate, target_rate
-                );
-                let source_signal =
-                    signal::from_iter(samples_i16.into_iter().map(|s| [s.to_sample::<f32>()]));
-                let linear = Linear::new([0.0], [0.0]);
-                let converter = signal::interpolate::Converter::from_hz_to_hz(
-                    source_signal,
-                    linear,
-                    source_sample_rate as f64,
-                    target_rate as f64,
-                );
-                let resampled_mono: Vec<f32> =
-                    converter.until_exhausted().map(|frame| frame[0]).collect();
-                Box::new(resampled_mono.into_iter())
-            }
-        } else {
-            Box::new(samples_i16.into_iter().map(|s| s.to_sample::<f32>()))
-        };
-
-    state.audio_samples.extend(mono_samples_iterator);
-
-    let AudioProcessingState {
-        audio_samples,
-        fft_buffer,
-        window,
-        fft_plan,
-        filterbank,
-        smoothed_melbanks,
-        peak_energy,
-        num_bands,
-        .. // Ignore fft_size as we already have it
-    } = &mut *state;
-
-    while audio_samples.len() >= fft_size {
-        // Now we are borrowing the fields individually, which is safe.
-        for (i, sample) in audio_samples.iter().take(fft_size).enumerate() {
-            fft_buffer[i] = Complex::new(sample * window[i], 0.0);
-        }
-
-        fft_plan.process(fft_buffer);
-
-        let magnitudes: Vec<f32> = fft_buffer[0..fft_size / 2]
-            .iter()
-            .map(|c| c.norm_sqr().sqrt())
-            .collect();
-        let raw_melbanks: Vec<f32> = filterbank
-            .iter()
-            .map(|filter| filter.iter().map(|&(bin, w)| magnitudes[bin] * w).sum())
-            .collect();
-
-        for i in 0..*num_bands {
-            smoothed_melbanks[i] = (smoothed_melbanks[i] * settings.smoothing_factor)
-                + (raw_melbanks[i] * (1.0 - settings.smoothing_factor));
-        }
-
-        let current_max_energy = smoothed_melbanks
-            .iter()
-            .fold(0.0f32, |max, &val| val.max(max));
-
-        if current_max_energy > *peak_energy {
-            *peak_energy = *peak_energy * (1.0 - settings.agc_attack)
-                + current_max_energy * settings.agc_attack;
-        } else {
-            *peak_energy =
-                *peak_energy * (1.0 - settings.agc_decay) + current_max_energy * settings.agc_decay;
-        }
-        *peak_energy = peak_energy.max(1e-4);
-
-        let final_melbanks: Vec<f32> = smoothed_melbanks
-            .iter()
-            .map(|&val| (val / *peak_energy).min(1.0))
-            .collect();
-
-        if let Ok(mut data) = SHARED_AUDIO_DATA.lock() {
-            data.melbanks = final_melbanks;
-        }
-
-        audio_samples.drain(0..fft_size);
-    }
+    SINK.lock().unwrap().push(&samples, 1, sampling_rate as u32);
 }
 
 fn find_device(host: &cpal::Host, name: &str) -> Option<Device> {
@@ -1,13 +1,28 @@
-use super::{AudioAnalysisData, AudioCommand, DspSettings};
+use super::sources::file::{file_device_name, parse_file_device_name, FilePlayback};
+use super::sources::generator::{
+    generator_device_name, parse_generator_device_name, SignalGenerator, TestSignal,
+};
+use super::sources::network::{NetworkReceiver, NETWORK_DEVICE_NAME};
+use super::sources::replay::{parse_replay_device_name, replay_device_name, AnalysisReplay};
+use super::{
+    AudioAnalysisData, AudioCommand, AudioDeviceChange, AudioDevicesInfo, AudioInput,
+    AudioStreamError, DspSettings, SharedAudioData, SharedAudioSources,
+};
 use crate::audio::shared_processing::build_and_play_stream_shared;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, Stream};
+use std::collections::HashMap;
 use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
 
 pub fn get_desktop_devices_impl() -> Result<super::AudioDevicesInfo, String> {
     let host = cpal::default_host();
     let mut input_devices: Vec<super::AudioDevice> = Vec::new();
-    #[cfg_attr(not(target_os = "windows"), allow(unused_mut))]
+    #[cfg_attr(
+        not(any(target_os = "windows", target_os = "linux")),
+        allow(unused_mut)
+    )]
     let mut loopback_devices: Vec<super::AudioDevice> = Vec::new();
 
     if let Ok(devices) = host.input_devices() {
@@ -32,7 +47,23 @@ pub fn get_desktop_devices_impl() -> Result<super::AudioDevicesInfo, String> {
         }
     }
 
+    #[cfg(target_os = "linux")]
+    let pulse_monitors = super::pulse::list_monitor_sources();
+    #[cfg(target_os = "linux")]
+    loopback_devices.extend(pulse_monitors.iter().map(|monitor| super::AudioDevice {
+        name: monitor.loopback_name(),
+    }));
+
     let mut default_device_name: Option<String> = None;
+
+    #[cfg(target_os = "linux")]
+    if let Some(default_sink) = super::pulse::default_sink_name() {
+        default_device_name = pulse_monitors
+            .iter()
+            .find(|monitor| monitor.sink_name == default_sink)
+            .map(|monitor| monitor.loopback_name());
+    }
+
     if let Some(default_output) = host.default_output_device() {
         if let Ok(target_name) = default_output.name() {
             let target_loopback_name = format!("System Audio ({})", target_name);
@@ -65,6 +96,16 @@ pub fn get_desktop_devices_impl() -> Result<super::AudioDevicesInfo, String> {
 
     let mut all_devices = loopback_devices;
     all_devices.extend(input_devices);
+    all_devices.push(super::AudioDevice {
+        name: NETWORK_DEVICE_NAME.to_string(),
+    });
+    all_devices.extend(
+        TestSignal::presets()
+            .iter()
+            .map(|signal| super::AudioDevice {
+                name: generator_device_name(signal),
+            }),
+    );
 
     Ok(super::AudioDevicesInfo {
         devices: all_devices,
@@ -81,107 +122,561 @@ pub fn set_desktop_device_impl(
         .map_err(|e| e.to_string())
 }
 
+/// Whatever is currently feeding the DSP pipeline on the audio thread.
+enum ActiveSource {
+    Stream(Stream),
+    File(FilePlayback),
+    Generator(SignalGenerator),
+    Network(NetworkReceiver),
+    Replay(AnalysisReplay),
+    #[cfg(target_os = "linux")]
+    Monitor(super::pulse::MonitorCapture),
+}
+
+impl ActiveSource {
+    fn stop(self) {
+        match self {
+            ActiveSource::Stream(stream) => {
+                if let Err(e) = stream.pause() {
+                    eprintln!("[AUDIO] Failed to pause stream: {}", e);
+                }
+                drop(stream);
+            }
+            ActiveSource::File(playback) => drop(playback),
+            ActiveSource::Generator(generator) => drop(generator),
+            ActiveSource::Network(receiver) => drop(receiver),
+            ActiveSource::Replay(replay) => drop(replay),
+            #[cfg(target_os = "linux")]
+            ActiveSource::Monitor(capture) => drop(capture),
+        }
+    }
+}
+
+fn open_source(
+    host: &cpal::Host,
+    device_name: &str,
+    file_looping: bool,
+    audio_data: &Arc<Mutex<AudioAnalysisData>>,
+    dsp_settings: &Arc<Mutex<DspSettings>>,
+    error_tx: mpsc::Sender<String>,
+) -> Result<ActiveSource, String> {
+    if let Some(path) = parse_file_device_name(device_name) {
+        return FilePlayback::start(path, file_looping, audio_data.clone(), dsp_settings.clone())
+            .map(ActiveSource::File);
+    }
+
+    if let Some(path) = parse_replay_device_name(device_name) {
+        return AnalysisReplay::start(path, file_looping, audio_data.clone())
+            .map(ActiveSource::Replay);
+    }
+
+    if let Some(signal) = parse_generator_device_name(device_name) {
+        return SignalGenerator::start(signal, audio_data.clone(), dsp_settings.clone())
+            .map(ActiveSource::Generator);
+    }
+
+    if device_name == NETWORK_DEVICE_NAME {
+        return NetworkReceiver::start(audio_data.clone(), dsp_settings.clone())
+            .map(ActiveSource::Network);
+    }
+
+    #[cfg(target_os = "linux")]
+    if device_name.starts_with(super::pulse::LOOPBACK_PREFIX) {
+        return open_pulse_monitor(device_name, audio_data, dsp_settings, error_tx);
+    }
+
+    let is_loopback = cfg!(target_os = "windows") && device_name.starts_with("System Audio (");
+    let device = find_device(host, device_name, is_loopback)
+        .ok_or_else(|| format!("Could not find device: {}", device_name))?;
+    let config = if is_loopback {
+        device.default_output_config()
+    } else {
+        device.default_input_config()
+    }
+    .map_err(|e| format!("No default config for {}: {}", device_name, e))?;
+    build_and_play_stream_shared(
+        device,
+        config,
+        audio_data.clone(),
+        dsp_settings.clone(),
+        Some(error_tx),
+    )
+    .map(ActiveSource::Stream)
+}
+
+#[cfg(target_os = "linux")]
+fn open_pulse_monitor(
+    device_name: &str,
+    audio_data: &Arc<Mutex<AudioAnalysisData>>,
+    dsp_settings: &Arc<Mutex<DspSettings>>,
+    error_tx: mpsc::Sender<String>,
+) -> Result<ActiveSource, String> {
+    let monitor = super::pulse::find_monitor(device_name)
+        .ok_or_else(|| format!("Could not find monitor source: {}", device_name))?;
+    println!("[AUDIO] Recording monitor source {}", monitor.source_name);
+    super::pulse::MonitorCapture::start(
+        &monitor,
+        audio_data.clone(),
+        dsp_settings.clone(),
+        error_tx,
+    )
+    .map(ActiveSource::Monitor)
+}
+
+/// How often devices are enumerated while a capture waits for its device to come back.
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(3);
+/// How often devices are enumerated otherwise, to notice unplugged or new devices.
+const IDLE_DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(15);
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+struct Retry {
+    attempt: u32,
+    at: Instant,
+}
+
+fn list_desktop_devices() -> AudioDevicesInfo {
+    get_desktop_devices_impl().unwrap_or(AudioDevicesInfo {
+        devices: vec![],
+        default_device_name: None,
+    })
+}
+
+fn device_names(info: &AudioDevicesInfo) -> Vec<&str> {
+    info.devices.iter().map(|d| d.name.as_str()).collect()
+}
+
+/// Enumerates the devices once for all captures, so hot-plug detection costs the same
+/// however many inputs are running.
+struct DeviceWatcher<F> {
+    list: F,
+    devices: AudioDevicesInfo,
+    last_poll: Instant,
+}
+
+impl<F: FnMut() -> AudioDevicesInfo> DeviceWatcher<F> {
+    fn new(mut list: F) -> Self {
+        let devices = list();
+        Self {
+            list,
+            devices,
+            last_poll: Instant::now(),
+        }
+    }
+
+    fn devices(&self) -> &AudioDevicesInfo {
+        &self.devices
+    }
+
+    /// Enumerates the devices again if the poll interval has passed, using the short one
+    /// while a capture is `recovering`. Returns the new list if it changed.
+    fn poll(&mut self, now: Instant, recovering: bool) -> Option<&AudioDevicesInfo> {
+        let interval = if recovering {
+            DEVICE_POLL_INTERVAL
+        } else {
+            IDLE_DEVICE_POLL_INTERVAL
+        };
+        if now.saturating_duration_since(self.last_poll) < interval {
+            return None;
+        }
+        self.last_poll = now;
+        let devices = (self.list)();
+        if device_names(&devices) == device_names(&self.devices) {
+            return None;
+        }
+        self.devices = devices;
+        Some(&self.devices)
+    }
+}
+
+/// State of one capture stream: what the user asked for and what is actually running.
+///
+/// If the requested device fails, the main input falls back to the default device; every
+/// input keeps retrying the requested one with an exponential backoff until it comes back.
+struct Capture {
+    host: cpal::Host,
+    app_handle: AppHandle,
+    /// Name of the additional input, `None` for the main input.
+    input: Option<String>,
+    audio_data: Arc<Mutex<AudioAnalysisData>>,
+    dsp_settings: Arc<Mutex<DspSettings>>,
+    source: Option<ActiveSource>,
+    stream_errors: Option<mpsc::Receiver<String>>,
+    active_device_name: Option<String>,
+    requested_device_name: Option<String>,
+    file_looping: bool,
+    retry: Option<Retry>,
+    devices: Option<AudioDevicesInfo>,
+}
+
+impl Capture {
+    fn new(
+        app_handle: AppHandle,
+        input: Option<String>,
+        audio_data: Arc<Mutex<AudioAnalysisData>>,
+        dsp_settings: Arc<Mutex<DspSettings>>,
+        devices: &AudioDevicesInfo,
+    ) -> Self {
+        Self {
+            host: cpal::default_host(),
+            app_handle,
+            input,
+            audio_data,
+            dsp_settings,
+            source: None,
+            stream_errors: None,
+            active_device_name: None,
+            requested_device_name: None,
+            file_looping: true,
+            retry: None,
+            devices: Some(devices.clone()),
+        }
+    }
+
+    /// True while the requested device isn't the one running, e.g. during a fallback.
+    fn recovering(&self) -> bool {
+        self.retry.is_some() || self.active_device_name != self.requested_device_name
+    }
+
+    /// Runs the periodic recovery work: stream errors, hot-plug and pending retries.
+    /// `changed_devices` is the device list if it changed since the last tick.
+    fn tick(&mut self, changed_devices: Option<&AudioDevicesInfo>) {
+        self.check_stream_errors();
+        if let Some(devices) = changed_devices {
+            self.update_devices(devices);
+        }
+        if self
+            .retry
+            .as_ref()
+            .is_some_and(|retry| Instant::now() >= retry.at)
+        {
+            self.retry_requested_device();
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(source) = self.source.take() {
+            source.stop();
+        }
+        self.stream_errors = None;
+        self.active_device_name = None;
+    }
+
+    fn open(&mut self, device_name: &str) -> Result<(), String> {
+        self.stop();
+        let (error_tx, error_rx) = mpsc::channel();
+        let source = open_source(
+            &self.host,
+            device_name,
+            self.file_looping,
+            &self.audio_data,
+            &self.dsp_settings,
+            error_tx,
+        )?;
+        self.source = Some(source);
+        self.stream_errors = Some(error_rx);
+        self.active_device_name = Some(device_name.to_string());
+        Ok(())
+    }
+
+    /// Switches to a device on request of the user.
+    fn select(&mut self, device_name: String) {
+        self.requested_device_name = Some(device_name.clone());
+        self.retry = None;
+        match self.open(&device_name) {
+            Ok(()) => self.emit_device_change(),
+            Err(e) => self.fail(&device_name, e),
+        }
+    }
+
+    fn report_error(&self, device_name: &str, message: &str) {
+        eprintln!("[AUDIO] Stream error on {}: {}", device_name, message);
+        let _ = self.app_handle.emit(
+            "audio-stream-error",
+            &AudioStreamError {
+                input: self.input.clone(),
+                device_name: device_name.to_string(),
+                message: message.to_string(),
+            },
+        );
+    }
+
+    /// Reports a failure of the requested device and starts recovering from it.
+    fn fail(&mut self, device_name: &str, message: String) {
+        self.report_error(device_name, &message);
+        if self.active_device_name.as_deref() == Some(device_name) {
+            self.stop();
+        }
+        self.fall_back_to_default();
+        self.schedule_retry();
+    }
+
+    fn schedule_retry(&mut self) {
+        let attempt = self.retry.as_ref().map_or(0, |r| r.attempt + 1);
+        let delay = RETRY_BASE_DELAY
+            .saturating_mul(1 << attempt.min(16))
+            .min(RETRY_MAX_DELAY);
+        println!(
+            "[AUDIO] Retrying audio device in {:.1}s (attempt {})",
+            delay.as_secs_f32(),
+            attempt + 1
+        );
+        self.retry = Some(Retry {
+            attempt,
+            at: Instant::now() + delay,
+        });
+    }
+
+    fn fall_back_to_default(&mut self) {
+        // An additional input on the default device would just duplicate the main input.
+        if self.source.is_some() || self.input.is_some() {
+            return;
+        }
+        let Some(default_name) = self
+            .devices
+            .as_ref()
+            .and_then(|info| info.default_device_name.clone())
+        else {
+            return;
+        };
+        if self.requested_device_name.as_ref() == Some(&default_name) {
+            return;
+        }
+        println!("[AUDIO] Falling back to default device: {}", default_name);
+        match self.open(&default_name) {
+            Ok(()) => self.emit_device_change(),
+            Err(e) => self.report_error(&default_name, &e),
+        }
+    }
+
+    fn retry_requested_device(&mut self) {
+        let Some(requested) = self.requested_device_name.clone() else {
+            self.retry = None;
+            return;
+        };
+        // Don't tear down a working fallback for a device that is still unplugged.
+        let listed = self
+            .devices
+            .as_ref()
+            .is_none_or(|info| info.devices.iter().any(|d| d.name == requested));
+        if self.source.is_some() && !listed {
+            self.schedule_retry();
+            return;
+        }
+        match self.open(&requested) {
+            Ok(()) => {
+                println!("[AUDIO] Recovered audio device: {}", requested);
+                self.retry = None;
+                self.emit_device_change();
+            }
+            Err(e) => {
+                self.report_error(&requested, &e);
+                self.fall_back_to_default();
+                self.schedule_retry();
+            }
+        }
+    }
+
+    fn emit_device_change(&self) {
+        let _ = self.app_handle.emit(
+            "audio-device-changed",
+            &AudioDeviceChange {
+                input: self.input.clone(),
+                devices: self.devices.clone(),
+                active_device_name: self.active_device_name.clone(),
+            },
+        );
+    }
+
+    fn update_devices(&mut self, info: &AudioDevicesInfo) {
+        self.devices = Some(info.clone());
+        let names: Vec<String> = info.devices.iter().map(|d| d.name.clone()).collect();
+        self.emit_device_change();
+
+        // Not every backend reports a stream error when its device goes away.
+        if let (Some(ActiveSource::Stream(_)), Some(active)) =
+            (&self.source, self.active_device_name.clone())
+        {
+            if !names.contains(&active) {
+                self.fail(&active, "Device disconnected".to_string());
+                return;
+            }
+        }
+        if let Some(requested) = &self.requested_device_name {
+            if self.active_device_name.as_ref() != Some(requested) && names.contains(requested) {
+                println!("[AUDIO] Requested device is back: {}", requested);
+                self.retry = Some(Retry {
+                    attempt: 0,
+                    at: Instant::now(),
+                });
+            }
+        }
+    }
+
+    fn check_stream_errors(&mut self) {
+        let Some(message) = self
+            .stream_errors
+            .as_ref()
+            .and_then(|errors| errors.try_iter().last())
+        else {
+            return;
+        };
+        if let Some(device_name) = self.active_device_name.clone() {
+            self.fail(&device_name, message);
+        }
+    }
+
+    fn handle_command(&mut self, command: AudioCommand) {
+        match command {
+            AudioCommand::ChangeDevice(device_name) => {
+                println!(
+                    "[AUDIO] Received command to change audio device to: {}",
+                    device_name
+                );
+                self.select(device_name);
+            }
+            AudioCommand::PlayFile { path, looping } => {
+                println!("[AUDIO] Received command to play audio file: {}", path);
+                self.file_looping = looping;
+                self.select(file_device_name(&path));
+            }
+            AudioCommand::ReplayAnalysis { path, looping } => {
+                println!(
+                    "[AUDIO] Received command to replay audio analysis: {}",
+                    path
+                );
+                self.file_looping = looping;
+                self.select(replay_device_name(&path));
+            }
+            AudioCommand::SeekFile(position_secs) => match &self.source {
+                Some(ActiveSource::File(playback)) => playback.seek(position_secs),
+                _ => println!("[AUDIO] Cannot seek, no audio file is playing."),
+            },
+            AudioCommand::SetFileLooping(looping) => {
+                self.file_looping = looping;
+                if let Some(ActiveSource::File(playback)) = &self.source {
+                    playback.set_looping(looping);
+                }
+            }
+            AudioCommand::UpdateSettings(new_settings) => {
+                println!("[AUDIO] Received new DSP settings.");
+                let network_changed = {
+                    let mut settings = self.dsp_settings.lock().unwrap();
+                    let changed = settings.network_input != new_settings.network_input;
+                    *settings = new_settings;
+                    changed
+                };
+                if network_changed && matches!(self.source, Some(ActiveSource::Network(_))) {
+                    println!("[AUDIO] Network input settings changed, restarting receiver.");
+                    self.select(NETWORK_DEVICE_NAME.to_string());
+                }
+            }
+            AudioCommand::SetInput { .. } | AudioCommand::RemoveInput(_) => {}
+            AudioCommand::RestartStream => {
+                if let Some(device_name) = self.requested_device_name.clone() {
+                    println!(
+                        "[AUDIO] Received command to restart audio stream for device: {}",
+                        device_name
+                    );
+                    self.select(device_name);
+                } else {
+                    println!("[AUDIO] Cannot restart stream, no device is currently active.");
+                }
+            }
+        }
+    }
+}
+
+/// Starts the named additional input, or applies new settings and device to it.
+fn set_input(
+    inputs: &mut HashMap<String, Capture>,
+    audio_sources: &SharedAudioSources,
+    app_handle: &AppHandle,
+    devices: &AudioDevicesInfo,
+    name: String,
+    input: AudioInput,
+) {
+    if let Some(capture) = inputs.get_mut(&name) {
+        capture.handle_command(AudioCommand::UpdateSettings(input.dsp_settings));
+        if capture.requested_device_name.as_ref() != Some(&input.device_name) {
+            capture.select(input.device_name);
+        }
+        return;
+    }
+
+    println!(
+        "[AUDIO] Starting audio input '{}' on {}",
+        name, input.device_name
+    );
+    let audio_data = SharedAudioData::default();
+    audio_sources
+        .0
+        .lock()
+        .unwrap()
+        .insert(name.clone(), audio_data.clone());
+    let mut capture = Capture::new(
+        app_handle.clone(),
+        Some(name.clone()),
+        audio_data.0,
+        Arc::new(Mutex::new(input.dsp_settings)),
+        devices,
+    );
+    capture.select(input.device_name);
+    inputs.insert(name, capture);
+}
+
 pub fn run_desktop_capture(
     command_rx: mpsc::Receiver<AudioCommand>,
     audio_data: Arc<Mutex<AudioAnalysisData>>,
     dsp_settings: Arc<Mutex<DspSettings>>,
+    audio_sources: SharedAudioSources,
+    app_handle: AppHandle,
 ) {
-    let host = cpal::default_host();
-    let mut current_stream: Option<Stream> = None;
-    let mut current_device_name: Option<String> = None;
+    let mut watcher = DeviceWatcher::new(list_desktop_devices);
+    let mut capture = Capture::new(
+        app_handle.clone(),
+        None,
+        audio_data,
+        dsp_settings,
+        watcher.devices(),
+    );
+    let mut inputs: HashMap<String, Capture> = HashMap::new();
 
     loop {
         if let Ok(command) = command_rx.try_recv() {
             match command {
-                AudioCommand::ChangeDevice(device_name) => {
-                    println!(
-                        "[AUDIO] Received command to change audio device to: {}",
-                        device_name
+                AudioCommand::SetInput { name, input } => {
+                    set_input(
+                        &mut inputs,
+                        &audio_sources,
+                        &app_handle,
+                        watcher.devices(),
+                        name,
+                        input,
                     );
-                    current_device_name = Some(device_name.clone());
-
-                    if let Some(stream) = current_stream.take() {
-                        stream.pause().expect("Failed to pause stream");
-                        drop(stream);
-                    }
-
-                    let is_loopback =
-                        cfg!(target_os = "windows") && device_name.starts_with("System Audio (");
-
-                    if let Some(device) = find_device(&host, &device_name, is_loopback) {
-                        let config = if is_loopback {
-                            device
-                                .default_output_config()
-                                .expect("no default output config")
-                        } else {
-                            device
-                                .default_input_config()
-                                .expect("no default input config")
-                        };
-                        let stream = build_and_play_stream_shared(
-                            device,
-                            config,
-                            audio_data.clone(),
-                            dsp_settings.clone(),
-                        );
-                        current_stream = Some(stream);
-                    } else {
-                        eprintln!("[AUDIO] Could not find requested device: {}", device_name);
-                        current_device_name = None;
-                    }
-                }
-                AudioCommand::UpdateSettings(new_settings) => {
-                    println!("[AUDIO] Received new DSP settings.");
-                    let mut settings = dsp_settings.lock().unwrap();
-                    *settings = new_settings;
                 }
-                AudioCommand::RestartStream => {
-                    if let Some(device_name) = current_device_name.clone() {
-                        println!(
-                            "[AUDIO] Received command to restart audio stream for device: {}",
-                            device_name
-                        );
-
-                        if let Some(stream) = current_stream.take() {
-                            stream.pause().expect("Failed to pause stream");
-                            drop(stream);
-                        }
-
-                        let is_loopback = cfg!(target_os = "windows")
-                            && device_name.starts_with("System Audio (");
-
-                        if let Some(device) = find_device(&host, &device_name, is_loopback) {
-                            let config = if is_loopback {
-                                device
-                                    .default_output_config()
-                                    .expect("no default output config")
-                            } else {
-                                device
-                                    .default_input_config()
-                                    .expect("no default input config")
-                            };
-                            let stream = build_and_play_stream_shared(
-                                device,
-                                config,
-                                audio_data.clone(),
-                                dsp_settings.clone(),
-                            );
-                            current_stream = Some(stream);
-                        } else {
-                            eprintln!(
-                                "[AUDIO] Could not find previous device to restart: {}",
-                                device_name
-                            );
-                            current_device_name = None;
-                        }
-                    } else {
-                        println!("[AUDIO] Cannot restart stream, no device is currently active.");
+                AudioCommand::RemoveInput(name) => {
+                    if let Some(mut input) = inputs.remove(&name) {
+                        println!("[AUDIO] Stopping audio input '{}'", name);
+                        input.stop();
                     }
+                    audio_sources.0.lock().unwrap().remove(&name);
                 }
+                command => capture.handle_command(command),
             }
         }
 
-        std::thread::sleep(std::time::Duration::from_millis(10));
+        let recovering = capture.recovering() || inputs.values().any(Capture::recovering);
+        let changed_devices = watcher.poll(Instant::now(), recovering).cloned();
+        if changed_devices.is_some() {
+            println!("[AUDIO] Audio device list changed.");
+        }
+        capture.tick(changed_devices.as_ref());
+        for input in inputs.values_mut() {
+            input.tick(changed_devices.as_ref());
+        }
+
+        std::thread::sleep(Duration::from_millis(10));
     }
 }
 
@@ -201,3 +696,76 @@ fn find_device(host: &cpal::Host, name: &str, is_loopback: bool) -> Option<Devic
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioDevice;
+    use std::cell::{Cell, RefCell};
+
+    fn device_list(names: &[&str]) -> AudioDevicesInfo {
+        AudioDevicesInfo {
+            devices: names
+                .iter()
+                .map(|name| AudioDevice {
+                    name: name.to_string(),
+                })
+                .collect(),
+            default_device_name: names.first().map(|name| name.to_string()),
+        }
+    }
+
+    #[test]
+    fn reports_plugged_and_unplugged_devices() {
+        let plugged = RefCell::new(vec!["Mic"]);
+        let polls = Cell::new(0);
+        let mut watcher = DeviceWatcher::new(|| {
+            polls.set(polls.get() + 1);
+            device_list(&plugged.borrow())
+        });
+        let start = Instant::now();
+        let mut at = start;
+        let mut poll = |watcher: &mut DeviceWatcher<_>, recovering| {
+            at += IDLE_DEVICE_POLL_INTERVAL;
+            watcher
+                .poll(at, recovering)
+                .map(|info| device_names(info).join(", "))
+        };
+
+        assert_eq!(poll(&mut watcher, false), None);
+        plugged.borrow_mut().push("USB Interface");
+        assert_eq!(
+            poll(&mut watcher, false).as_deref(),
+            Some("Mic, USB Interface")
+        );
+        plugged.borrow_mut().remove(0);
+        assert_eq!(poll(&mut watcher, true).as_deref(), Some("USB Interface"));
+        assert_eq!(
+            watcher.devices().default_device_name.as_deref(),
+            Some("USB Interface")
+        );
+        assert_eq!(polls.get(), 4);
+    }
+
+    #[test]
+    fn polls_quickly_only_while_recovering() {
+        let polls = Cell::new(0);
+        let mut watcher = DeviceWatcher::new(|| {
+            polls.set(polls.get() + 1);
+            device_list(&["Mic"])
+        });
+        let start = watcher.last_poll;
+
+        watcher.poll(start + DEVICE_POLL_INTERVAL, false);
+        assert_eq!(polls.get(), 1);
+        watcher.poll(start + DEVICE_POLL_INTERVAL, true);
+        assert_eq!(polls.get(), 2);
+        watcher.poll(start + DEVICE_POLL_INTERVAL * 2, false);
+        assert_eq!(polls.get(), 2);
+        watcher.poll(
+            start + DEVICE_POLL_INTERVAL + IDLE_DEVICE_POLL_INTERVAL,
+            false,
+        );
+        assert_eq!(polls.get(), 3);
+    }
+}
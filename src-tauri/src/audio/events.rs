@@ -0,0 +1,118 @@
+use super::{AudioAnalysisData, DspSettings};
+use serde::Serialize;
+use specta::Type;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+
+// How often the publisher looks for new analysis frames. Faster than any analysis
+// frame rate, so no beat or onset is missed between two updates.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Payload of the `audio-analysis` event and the API's analysis stream.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct AudioAnalysisUpdate {
+    pub sequence: u32,
+    /// Band values, averaged down to `AnalysisEventSettings::bands` if set.
+    pub melbanks: Vec<f32>,
+    // Levels
+    pub rms: f32,
+    pub peak: f32,
+    pub dbfs: f32,
+    pub agc_gain: f32,
+    pub silence: bool,
+    // Beat data; the flags are set if they fired in any frame since the last update.
+    pub beat: bool,
+    pub onset: bool,
+    pub onset_lows: bool,
+    pub onset_mids: bool,
+    pub onset_highs: bool,
+    pub beat_strength: f32,
+    pub bpm: f32,
+    pub beat_phase: f32,
+    pub bpm_confidence: f32,
+}
+
+impl AudioAnalysisUpdate {
+    fn new(data: &AudioAnalysisData, bands: u32) -> Self {
+        Self {
+            sequence: data.sequence,
+            melbanks: decimate_bands(&data.melbanks, bands as usize),
+            rms: data.rms,
+            peak: data.peak,
+            dbfs: data.dbfs,
+            agc_gain: data.agc_gain,
+            silence: data.silence,
+            beat: data.beat,
+            onset: data.onset,
+            onset_lows: data.onset_lows,
+            onset_mids: data.onset_mids,
+            onset_highs: data.onset_highs,
+            beat_strength: data.beat_strength,
+            bpm: data.bpm,
+            beat_phase: data.beat_phase,
+            bpm_confidence: data.bpm_confidence,
+        }
+    }
+}
+
+/// Averages `bands` down to `count` evenly split groups. 0, or at least as many
+/// as there are bands, keeps them as they are.
+fn decimate_bands(bands: &[f32], count: usize) -> Vec<f32> {
+    if count == 0 || count >= bands.len() {
+        return bands.to_vec();
+    }
+    (0..count)
+        .map(|i| {
+            let group = &bands[i * bands.len() / count..(i + 1) * bands.len() / count];
+            group.iter().sum::<f32>() / group.len() as f32
+        })
+        .collect()
+}
+
+/// Publishes the main input's analysis as `audio-analysis` events and to `broadcast_tx`
+/// (the API's WebSocket/SSE stream), at most `AnalysisEventSettings::rate_hz` times a second.
+pub fn run_analysis_publisher(
+    audio_data: Arc<Mutex<AudioAnalysisData>>,
+    dsp_settings: Arc<Mutex<DspSettings>>,
+    broadcast_tx: broadcast::Sender<AudioAnalysisUpdate>,
+    app_handle: AppHandle,
+) {
+    let mut last_sequence = audio_data.lock().unwrap().sequence;
+    let mut pending: Option<AudioAnalysisData> = None;
+    let mut last_publish = Instant::now();
+
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let settings = dsp_settings.lock().unwrap().analysis_events.clone();
+        if settings.rate_hz == 0 {
+            pending = None;
+            continue;
+        }
+
+        {
+            let data = audio_data.lock().unwrap();
+            if data.sequence != last_sequence {
+                last_sequence = data.sequence;
+                let mut latest = data.clone();
+                if let Some(earlier) = pending.take() {
+                    latest.merge_events(&earlier);
+                }
+                pending = Some(latest);
+            }
+        }
+
+        let interval = Duration::from_secs_f32(1.0 / settings.rate_hz as f32);
+        if last_publish.elapsed() < interval {
+            continue;
+        }
+        if let Some(data) = pending.take() {
+            last_publish = Instant::now();
+            let update = AudioAnalysisUpdate::new(&data, settings.bands);
+            let _ = app_handle.emit("audio-analysis", &update);
+            // Fails only while nobody is subscribed.
+            let _ = broadcast_tx.send(update);
+        }
+    }
+}
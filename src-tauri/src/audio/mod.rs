@@ -1,11 +1,28 @@
-use crate::utils::dsp::{BladePlusParams, FilterbankType};
+use crate::utils::dsp::{BladePlusParams, FilterbankType, WindowType};
+pub use agc::{Agc, AgcStrategy};
+pub use events::{run_analysis_publisher, AudioAnalysisUpdate};
+pub use onset::{OnsetDetector, OnsetResult};
+pub use processor::{AnalysisSink, AudioProcessor};
+pub use resample::{Resampler, ResamplerType};
 use serde::{Deserialize, Serialize};
 use specta::Type;
+pub use spectral::{note_name, SpectralAnalyzer, SpectralFeatures};
+use std::collections::HashMap;
 use std::sync::{mpsc, Arc, Mutex};
 use tauri::State;
+pub use tempo::{TempoResult, TempoTracker};
+mod agc;
+mod events;
+mod onset;
+mod processor;
+pub mod recording;
+mod resample;
 mod shared_processing;
+mod spectral;
+mod tempo;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(default)]
 pub struct DspSettings {
     // Critical Settings (require audio stream restart)
     pub fft_size: u32,
@@ -14,13 +31,40 @@ pub struct DspSettings {
     pub max_freq: f32,
     pub filterbank_type: FilterbankType,
     pub sample_rate: Option<u32>,
+    /// How the input is converted to `sample_rate`.
+    pub resampler_type: ResamplerType,
     pub blade_plus_params: Option<BladePlusParams>,
+    pub lows_max_freq: f32,
+    pub mids_max_freq: f32,
+    pub window_type: WindowType,
+    /// Samples between the starts of consecutive FFT frames. Smaller than `fft_size`
+    /// means overlapping frames: more analysis frames per second at the same resolution.
+    /// 0 (the default) uses `fft_size`, i.e. no overlap.
+    pub hop_size: u32,
 
     // Live Settings (can be changed on the fly)
     pub smoothing_factor: f32,
     pub agc_attack: f32,
     pub agc_decay: f32,
-    pub audio_delay_ms: u32,
+    pub agc_strategy: AgcStrategy,
+    /// Compute `AudioAnalysisData::spectral`; off by default as it costs extra CPU per frame.
+    pub spectral_features: bool,
+    /// Additionally analyse the left/right (and mid/side) channels into `AudioAnalysisData::channels`.
+    pub channel_analysis: ChannelAnalysis,
+    /// Compensates for output latency (e.g. a PA with heavy processing). Positive values
+    /// release every analysis frame this long after capture; negative values keep the
+    /// analysis live and hold back the LED output instead.
+    pub audio_delay_ms: i32,
+    /// Frames quieter than this (dBFS) count as silence. -100 disables the gate.
+    pub noise_gate_db: f32,
+    /// How long the level must stay below the threshold before the gate closes.
+    pub noise_gate_hold_ms: u32,
+
+    // Network input (used when the "Network (UDP/RTP)" device is selected)
+    pub network_input: NetworkInputSettings,
+
+    // Push updates (`audio-analysis` event and the API's analysis stream)
+    pub analysis_events: AnalysisEventSettings,
 }
 
 impl Default for DspSettings {
@@ -32,15 +76,109 @@ impl Default for DspSettings {
             max_freq: 15000.0,
             filterbank_type: FilterbankType::Blade,
             sample_rate: Some(30000),
+            resampler_type: ResamplerType::Linear,
             blade_plus_params: Some(BladePlusParams {
                 log_base: 12.0,
                 multiplier: 3700.0,
                 divisor: 230.0,
             }),
+            lows_max_freq: 250.0,
+            mids_max_freq: 2000.0,
+            window_type: WindowType::Hann,
+            hop_size: 0,
             smoothing_factor: 0.4,
             agc_attack: 0.01,
             agc_decay: 0.1,
+            agc_strategy: AgcStrategy::GlobalPeak,
+            spectral_features: false,
+            channel_analysis: ChannelAnalysis::Off,
             audio_delay_ms: 0,
+            noise_gate_db: -70.0,
+            noise_gate_hold_ms: 500,
+            network_input: NetworkInputSettings::default(),
+            analysis_events: AnalysisEventSettings::default(),
+        }
+    }
+}
+
+/// Which per-channel filterbanks to compute next to the mono mix.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, Default, PartialEq)]
+pub enum ChannelAnalysis {
+    #[default]
+    Off,
+    Stereo,
+    StereoMidSide,
+}
+
+/// Audio channel a virtual (or effect) takes its bands from.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, Default, PartialEq, Eq, Hash)]
+pub enum AudioChannel {
+    #[default]
+    Mix,
+    Left,
+    Right,
+    Mid,
+    Side,
+}
+
+/// Sample encoding of incoming network PCM.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, PartialEq)]
+pub enum PcmFormat {
+    S16Le,
+    S16Be,
+    F32Le,
+    F32Be,
+}
+
+impl PcmFormat {
+    pub fn bytes_per_sample(&self) -> usize {
+        match self {
+            PcmFormat::S16Le | PcmFormat::S16Be => 2,
+            PcmFormat::F32Le | PcmFormat::F32Be => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type, PartialEq)]
+#[serde(default)]
+pub struct NetworkInputSettings {
+    pub port: u16,
+    pub format: PcmFormat,
+    pub channels: u16,
+    pub sample_rate: u32,
+    /// Expect an RTP header in front of every packet (RFC 3551 L16 is big-endian).
+    pub rtp: bool,
+    /// How much audio to buffer before playout to ride out network jitter.
+    pub jitter_buffer_ms: u32,
+}
+
+impl Default for NetworkInputSettings {
+    fn default() -> Self {
+        Self {
+            port: 5004,
+            format: PcmFormat::S16Be,
+            channels: 2,
+            sample_rate: 48000,
+            rtp: true,
+            jitter_buffer_ms: 40,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type, PartialEq)]
+#[serde(default)]
+pub struct AnalysisEventSettings {
+    /// Maximum updates per second; 0 turns the updates off.
+    pub rate_hz: u32,
+    /// Average the bands down to this many values; 0 sends every band.
+    pub bands: u32,
+}
+
+impl Default for AnalysisEventSettings {
+    fn default() -> Self {
+        Self {
+            rate_hz: 30,
+            bands: 0,
         }
     }
 }
@@ -48,6 +186,15 @@ impl Default for DspSettings {
 #[derive(Default, Clone)]
 pub struct SharedDspSettings(pub Arc<Mutex<DspSettings>>);
 
+/// A capture stream analysed in addition to the main input, e.g. a drum mic next to the
+/// main mix. Virtuals pick it by name through `Virtual::audio_source`.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, Default)]
+#[serde(default)]
+pub struct AudioInput {
+    pub device_name: String,
+    pub dsp_settings: DspSettings,
+}
+
 #[derive(Serialize, Clone, Type)]
 pub struct AudioDevice {
     pub name: String,
@@ -59,79 +206,305 @@ pub struct AudioDevicesInfo {
     pub default_device_name: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Type, Default)]
+/// Payload of the `audio-stream-error` event.
+#[derive(Serialize, Type, Clone)]
+pub struct AudioStreamError {
+    /// Name of the additional input, `None` for the main input.
+    pub input: Option<String>,
+    pub device_name: String,
+    pub message: String,
+}
+
+/// Payload of the `audio-device-changed` event, sent when the device list or the
+/// active device changes (e.g. after a hot-plug or a fallback to the default device).
+#[derive(Serialize, Type, Clone)]
+pub struct AudioDeviceChange {
+    /// Name of the additional input, `None` for the main input.
+    pub input: Option<String>,
+    pub devices: Option<AudioDevicesInfo>,
+    pub active_device_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct AudioAnalysisData {
     pub melbanks: Vec<f32>,
+    pub band_ranges: BandRanges,
+    // Onset / beat detection (spectral flux over the raw filterbank output)
+    pub beat: bool,
+    pub onset: bool,
+    pub onset_lows: bool,
+    pub onset_mids: bool,
+    pub onset_highs: bool,
+    pub beat_strength: f32,
+    // Tempo tracking
+    pub bpm: f32,
+    pub beat_phase: f32,
+    pub bpm_confidence: f32,
+    // Input level of the analysed frame, before AGC
+    pub rms: f32,
+    pub peak: f32,
+    /// RMS level in dBFS, floored at `MIN_DBFS`.
+    pub dbfs: f32,
+    /// Factor the AGC currently multiplies the band energies by.
+    pub agc_gain: f32,
+    // Noise gate
+    /// True while the noise gate is closed; bands are zeroed and the AGC is held.
+    pub silence: bool,
+    /// Seconds the input level has been below the noise gate threshold.
+    pub silence_duration: f32,
+    /// Only present when `DspSettings::spectral_features` is enabled.
+    pub spectral: Option<SpectralFeatures>,
+    /// Only present when `DspSettings::channel_analysis` is enabled.
+    pub channels: Option<ChannelMelbanks>,
+    /// Incremented (wrapping) for every published frame, so consumers can tell new frames apart.
+    pub sequence: u32,
+}
+
+/// Filterbank output of the individual channels, processed like `melbanks`.
+/// Mono input reports the same bands left and right and a silent side channel.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, Default)]
+pub struct ChannelMelbanks {
+    pub left: Vec<f32>,
+    pub right: Vec<f32>,
+    pub mid: Option<Vec<f32>>,
+    pub side: Option<Vec<f32>>,
+}
+
+impl Default for AudioAnalysisData {
+    fn default() -> Self {
+        Self {
+            melbanks: Vec::new(),
+            band_ranges: BandRanges::default(),
+            beat: false,
+            onset: false,
+            onset_lows: false,
+            onset_mids: false,
+            onset_highs: false,
+            beat_strength: 0.0,
+            bpm: 0.0,
+            beat_phase: 0.0,
+            bpm_confidence: 0.0,
+            rms: 0.0,
+            peak: 0.0,
+            dbfs: MIN_DBFS,
+            agc_gain: 1.0,
+            silence: false,
+            silence_duration: 0.0,
+            spectral: None,
+            channels: None,
+            sequence: 0,
+        }
+    }
 }
 
 impl AudioAnalysisData {
     pub fn new(num_bands: usize) -> Self {
         Self {
             melbanks: vec![0.0; num_bands],
+            ..Default::default()
+        }
+    }
+
+    /// Bands of `channel`, falling back to the mono mix when that channel isn't analysed.
+    pub fn channel_melbanks(&self, channel: AudioChannel) -> &[f32] {
+        let bands = self.channels.as_ref().and_then(|channels| match channel {
+            AudioChannel::Mix => None,
+            AudioChannel::Left => Some(&channels.left),
+            AudioChannel::Right => Some(&channels.right),
+            AudioChannel::Mid => channels.mid.as_ref(),
+            AudioChannel::Side => channels.side.as_ref(),
+        });
+        bands.unwrap_or(&self.melbanks)
+    }
+
+    /// Copy of this frame with `melbanks` taken from `channel`, so effects (and the
+    /// lows/mids/highs helpers) react to that channel only.
+    pub fn for_channel(&self, channel: AudioChannel) -> Self {
+        Self {
+            melbanks: self.channel_melbanks(channel).to_vec(),
+            ..self.clone()
         }
     }
+
+    pub fn apply_onsets(&mut self, onsets: &OnsetResult) {
+        self.beat = onsets.beat;
+        self.onset = onsets.onset;
+        self.onset_lows = onsets.onset_lows;
+        self.onset_mids = onsets.onset_mids;
+        self.onset_highs = onsets.onset_highs;
+        self.beat_strength = onsets.beat_strength;
+    }
+
+    pub fn apply_tempo(&mut self, tempo: &TempoResult) {
+        self.bpm = tempo.bpm;
+        self.beat_phase = tempo.beat_phase;
+        self.bpm_confidence = tempo.confidence;
+    }
+
+    /// Keeps the events of an `earlier` frame that is dropped in favour of this one,
+    /// so a beat or onset isn't lost when several frames are published at once.
+    pub fn merge_events(&mut self, earlier: &AudioAnalysisData) {
+        self.beat |= earlier.beat;
+        self.onset |= earlier.onset;
+        self.onset_lows |= earlier.onset_lows;
+        self.onset_mids |= earlier.onset_mids;
+        self.onset_highs |= earlier.onset_highs;
+        self.beat_strength = self.beat_strength.max(earlier.beat_strength);
+    }
 }
 
-const BASS_LOW: usize = 0;
-const BASS_HIGH: usize = 15;
-const MIDS_LOW: usize = 16;
-const MIDS_HIGH: usize = 63;
-const HIGHS_LOW: usize = 64;
-const HIGHS_HIGH: usize = 127;
+/// Inclusive band index ranges for the lows, mids and highs of the filterbank.
+///
+/// Computed from the filterbank's center frequencies whenever it is (re)built,
+/// so they stay correct for any band count and filterbank type.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, Default, PartialEq)]
+pub struct BandRanges {
+    pub lows: Option<(u32, u32)>,
+    pub mids: Option<(u32, u32)>,
+    pub highs: Option<(u32, u32)>,
+}
 
-pub fn lows_power(melbanks: &[f32]) -> f32 {
-    let high = BASS_HIGH.min(melbanks.len().saturating_sub(1));
-    let low = BASS_LOW.min(high);
-    if low >= high {
-        return 0.0;
+impl BandRanges {
+    pub fn from_settings(settings: &DspSettings) -> Self {
+        let centers = crate::utils::dsp::center_frequencies(
+            settings.num_bands as usize,
+            settings.min_freq,
+            settings.max_freq,
+            &settings.filterbank_type,
+        );
+        Self::from_center_frequencies(&centers, settings.lows_max_freq, settings.mids_max_freq)
+    }
+
+    pub fn from_center_frequencies(
+        centers: &[f32],
+        lows_max_freq: f32,
+        mids_max_freq: f32,
+    ) -> Self {
+        let range_of = |predicate: &dyn Fn(f32) -> bool| {
+            let mut indices = centers
+                .iter()
+                .enumerate()
+                .filter(|(_, &hz)| predicate(hz))
+                .map(|(i, _)| i as u32);
+            let first = indices.next()?;
+            Some((first, indices.last().unwrap_or(first)))
+        };
+        Self {
+            lows: range_of(&|hz| hz < lows_max_freq),
+            mids: range_of(&|hz| hz >= lows_max_freq && hz < mids_max_freq),
+            highs: range_of(&|hz| hz >= mids_max_freq),
+        }
     }
-    let slice = &melbanks[low..=high];
-    slice.iter().sum::<f32>() / slice.len() as f32
 }
 
-pub fn mids_power(melbanks: &[f32]) -> f32 {
-    let high = MIDS_HIGH.min(melbanks.len().saturating_sub(1));
-    let low = MIDS_LOW.min(high);
-    if low >= high {
-        return 0.0;
+pub fn band_slice(melbanks: &[f32], range: Option<(u32, u32)>) -> &[f32] {
+    match range {
+        Some((low, high)) if (high as usize) < melbanks.len() => {
+            &melbanks[low as usize..=high as usize]
+        }
+        _ => &[],
     }
-    let slice = &melbanks[low..=high];
-    slice.iter().sum::<f32>() / slice.len() as f32
 }
 
-pub fn highs_power(melbanks: &[f32]) -> f32 {
-    let high = HIGHS_HIGH.min(melbanks.len().saturating_sub(1));
-    let low = HIGHS_LOW.min(high);
-    if low >= high {
+fn range_power(melbanks: &[f32], range: Option<(u32, u32)>) -> f32 {
+    let slice = band_slice(melbanks, range);
+    if slice.is_empty() {
         return 0.0;
     }
-    let slice = &melbanks[low..=high];
     slice.iter().sum::<f32>() / slice.len() as f32
 }
 
+pub fn lows_power(audio_data: &AudioAnalysisData) -> f32 {
+    range_power(&audio_data.melbanks, audio_data.band_ranges.lows)
+}
+
+pub fn mids_power(audio_data: &AudioAnalysisData) -> f32 {
+    range_power(&audio_data.melbanks, audio_data.band_ranges.mids)
+}
+
+pub fn highs_power(audio_data: &AudioAnalysisData) -> f32 {
+    range_power(&audio_data.melbanks, audio_data.band_ranges.highs)
+}
+
+/// Level reported for digital silence.
+pub const MIN_DBFS: f32 = -100.0;
+
+/// Converts a linear amplitude to dBFS, floored at `MIN_DBFS`.
+pub fn amplitude_to_dbfs(amplitude: f32) -> f32 {
+    if amplitude <= 0.0 {
+        return MIN_DBFS;
+    }
+    (20.0 * amplitude.log10()).max(MIN_DBFS)
+}
+
 #[derive(Default, Clone)]
 pub struct SharedAudioData(pub Arc<Mutex<AudioAnalysisData>>);
 
+/// Analysis output of every additional audio input, by input name.
+#[derive(Default, Clone)]
+pub struct SharedAudioSources(pub Arc<Mutex<HashMap<String, SharedAudioData>>>);
+
+impl SharedAudioSources {
+    /// Latest analysis of the named input, if it exists.
+    pub fn get(&self, name: &str) -> Option<AudioAnalysisData> {
+        let sources = self.0.lock().unwrap();
+        let data = sources.get(name)?.0.lock().unwrap();
+        Some(data.clone())
+    }
+}
+
 #[cfg(target_os = "android")]
 pub mod android;
 #[cfg(not(target_os = "android"))]
 pub mod desktop;
+#[cfg(target_os = "linux")]
+pub mod pulse;
+#[cfg(not(target_os = "android"))]
+pub mod sources;
 
 pub enum AudioCommand {
     ChangeDevice(String),
     UpdateSettings(DspSettings),
     RestartStream,
+    PlayFile {
+        path: String,
+        looping: bool,
+    },
+    /// Feeds a recorded analysis stream into the shared analysis data instead of live capture.
+    ReplayAnalysis {
+        path: String,
+        looping: bool,
+    },
+    SeekFile(f64),
+    SetFileLooping(bool),
+    /// Starts the named additional input, or updates it if it is already running.
+    SetInput {
+        name: String,
+        input: AudioInput,
+    },
+    RemoveInput(String),
 }
 
 pub fn start_audio_capture(
     command_rx: mpsc::Receiver<AudioCommand>,
     audio_data: Arc<Mutex<AudioAnalysisData>>,
     dsp_settings: Arc<Mutex<DspSettings>>,
+    audio_sources: SharedAudioSources,
+    app_handle: tauri::AppHandle,
 ) {
     #[cfg(not(target_os = "android"))]
-    desktop::run_desktop_capture(command_rx, audio_data, dsp_settings);
+    desktop::run_desktop_capture(
+        command_rx,
+        audio_data,
+        dsp_settings,
+        audio_sources,
+        app_handle,
+    );
     #[cfg(target_os = "android")]
-    android::run_android_capture(command_rx, audio_data, dsp_settings);
+    {
+        let _ = (audio_sources, app_handle);
+        android::run_android_capture(command_rx, audio_data, dsp_settings);
+    }
 }
 
 #[tauri::command]
@@ -171,3 +544,60 @@ pub fn get_audio_analysis(audio_data: State<SharedAudioData>) -> Result<AudioAna
     let data = audio_data.0.lock().map_err(|e| e.to_string())?;
     Ok(data.clone())
 }
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_audio_input_analysis(
+    name: String,
+    audio_sources: State<SharedAudioSources>,
+) -> Result<AudioAnalysisData, String> {
+    audio_sources
+        .get(&name)
+        .ok_or_else(|| format!("Audio input '{}' not found", name))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn play_audio_file(
+    path: String,
+    looping: bool,
+    command_tx: State<mpsc::Sender<AudioCommand>>,
+) -> Result<(), String> {
+    command_tx
+        .send(AudioCommand::PlayFile { path, looping })
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn seek_audio_file(
+    position_secs: f64,
+    command_tx: State<mpsc::Sender<AudioCommand>>,
+) -> Result<(), String> {
+    command_tx
+        .send(AudioCommand::SeekFile(position_secs))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_audio_file_looping(
+    looping: bool,
+    command_tx: State<mpsc::Sender<AudioCommand>>,
+) -> Result<(), String> {
+    command_tx
+        .send(AudioCommand::SetFileLooping(looping))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn replay_audio_analysis(
+    path: String,
+    looping: bool,
+    command_tx: State<mpsc::Sender<AudioCommand>>,
+) -> Result<(), String> {
+    command_tx
+        .send(AudioCommand::ReplayAnalysis { path, looping })
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,215 @@
+use super::{band_slice, BandRanges};
+use std::collections::VecDeque;
+
+// How much history (in seconds) the adaptive threshold looks back over.
+const HISTORY_SECONDS: f32 = 1.0;
+// Threshold = mean + SENSITIVITY * standard deviation of the recent flux.
+const SENSITIVITY: f32 = 1.5;
+// Flux below this is treated as noise, no matter what the history says.
+const MIN_FLUX: f32 = 1e-3;
+// Log compression factor applied to the band energies before differencing.
+const COMPRESSION: f32 = 100.0;
+// Beats closer together than this are ignored (caps detection at 240 BPM).
+const MIN_BEAT_INTERVAL_SECONDS: f32 = 0.25;
+// Half-life of the beat strength envelope.
+const BEAT_STRENGTH_HALF_LIFE_SECONDS: f32 = 0.15;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OnsetResult {
+    pub onset: bool,
+    pub onset_lows: bool,
+    pub onset_mids: bool,
+    pub onset_highs: bool,
+    pub beat: bool,
+    pub beat_strength: f32,
+    /// Total positive spectral flux of this frame (the onset novelty curve).
+    pub novelty: f32,
+}
+
+/// Adaptive threshold over a sliding window of flux values for one frequency range.
+struct FluxTracker {
+    history: VecDeque<f32>,
+    capacity: usize,
+    last_flux: f32,
+}
+
+impl FluxTracker {
+    fn new(capacity: usize) -> Self {
+        Self {
+            history: VecDeque::with_capacity(capacity),
+            capacity,
+            last_flux: 0.0,
+        }
+    }
+
+    fn threshold(&self) -> f32 {
+        if self.history.is_empty() {
+            return f32::MAX;
+        }
+        let len = self.history.len() as f32;
+        let mean = self.history.iter().sum::<f32>() / len;
+        let variance = self.history.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / len;
+        (mean + SENSITIVITY * variance.sqrt()).max(MIN_FLUX)
+    }
+
+    /// Returns `(is_onset, threshold)` for the new flux value and records it.
+    fn push(&mut self, flux: f32) -> (bool, f32) {
+        let threshold = self.threshold();
+        // Require a rising flank so a single hit spanning two frames only fires once.
+        let is_onset =
+            self.history.len() >= self.capacity / 2 && flux > threshold && flux > self.last_flux;
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(flux);
+        self.last_flux = flux;
+        (is_onset, threshold)
+    }
+}
+
+/// Spectral-flux onset and beat detector running over the filterbank output.
+///
+/// Feed it the raw (unsmoothed, pre-AGC) band energies once per analysis frame.
+pub struct OnsetDetector {
+    previous_bands: Vec<f32>,
+    total: FluxTracker,
+    lows: FluxTracker,
+    mids: FluxTracker,
+    highs: FluxTracker,
+    ranges: BandRanges,
+    frames_since_beat: usize,
+    min_beat_frames: usize,
+    beat_strength: f32,
+    strength_decay: f32,
+}
+
+impl OnsetDetector {
+    pub fn new(num_bands: usize, ranges: BandRanges, frame_rate: f32) -> Self {
+        let frame_rate = frame_rate.max(1.0);
+        let capacity = ((HISTORY_SECONDS * frame_rate).round() as usize).max(4);
+        let min_beat_frames = (MIN_BEAT_INTERVAL_SECONDS * frame_rate).round() as usize;
+        Self {
+            previous_bands: vec![0.0; num_bands],
+            total: FluxTracker::new(capacity),
+            lows: FluxTracker::new(capacity),
+            mids: FluxTracker::new(capacity),
+            highs: FluxTracker::new(capacity),
+            ranges,
+            frames_since_beat: min_beat_frames,
+            min_beat_frames,
+            beat_strength: 0.0,
+            strength_decay: 0.5f32.powf(1.0 / (BEAT_STRENGTH_HALF_LIFE_SECONDS * frame_rate)),
+        }
+    }
+
+    pub fn process(&mut self, raw_bands: &[f32]) -> OnsetResult {
+        if raw_bands.len() != self.previous_bands.len() {
+            self.previous_bands = vec![0.0; raw_bands.len()];
+        }
+
+        let flux: Vec<f32> = raw_bands
+            .iter()
+            .zip(self.previous_bands.iter_mut())
+            .map(|(&value, previous)| {
+                let compressed = (1.0 + COMPRESSION * value).ln();
+                let diff = (compressed - *previous).max(0.0);
+                *previous = compressed;
+                diff
+            })
+            .collect();
+
+        let range_flux = |range: Option<(u32, u32)>| band_slice(&flux, range).iter().sum::<f32>();
+
+        let novelty = flux.iter().sum::<f32>();
+        let lows_flux = range_flux(self.ranges.lows);
+        let (onset, _) = self.total.push(novelty);
+        let (onset_lows, lows_threshold) = self.lows.push(lows_flux);
+        let (onset_mids, _) = self.mids.push(range_flux(self.ranges.mids));
+        let (onset_highs, _) = self.highs.push(range_flux(self.ranges.highs));
+
+        self.beat_strength *= self.strength_decay;
+        self.frames_since_beat = self.frames_since_beat.saturating_add(1);
+        let beat = onset_lows && self.frames_since_beat >= self.min_beat_frames;
+        if beat {
+            self.frames_since_beat = 0;
+            let strength = (lows_flux / (2.0 * lows_threshold)).min(1.0);
+            self.beat_strength = self.beat_strength.max(strength);
+        }
+
+        OnsetResult {
+            onset,
+            onset_lows,
+            onset_mids,
+            onset_highs,
+            beat,
+            beat_strength: self.beat_strength,
+            novelty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME_RATE: f32 = 50.0;
+    const NUM_BANDS: usize = 8;
+
+    fn detector() -> OnsetDetector {
+        let ranges = BandRanges {
+            lows: Some((0, 1)),
+            mids: Some((2, 4)),
+            highs: Some((5, 7)),
+        };
+        OnsetDetector::new(NUM_BANDS, ranges, FRAME_RATE)
+    }
+
+    /// Filterbank frames that are quiet except for a kick in the low bands at `clicks`.
+    fn frames(len: usize, clicks: &[usize]) -> Vec<Vec<f32>> {
+        (0..len)
+            .map(|frame| {
+                let mut bands = vec![0.01; NUM_BANDS];
+                if clicks.contains(&frame) {
+                    bands[0] = 1.0;
+                    bands[1] = 1.0;
+                }
+                bands
+            })
+            .collect()
+    }
+
+    /// Frames on which `beat` and `onset_lows` fired.
+    fn detect(clicks: &[usize], len: usize) -> (Vec<usize>, Vec<usize>) {
+        let mut detector = detector();
+        let mut beats = Vec::new();
+        let mut lows = Vec::new();
+        for (frame, bands) in frames(len, clicks).iter().enumerate() {
+            let result = detector.process(bands);
+            if result.beat {
+                beats.push(frame);
+            }
+            if result.onset_lows {
+                lows.push(frame);
+            }
+        }
+        (beats, lows)
+    }
+
+    #[test]
+    fn fires_on_clicks_only() {
+        // Leave the first second for the adaptive threshold to fill its history.
+        let clicks = [50, 75, 100, 125, 150];
+        let (beats, lows) = detect(&clicks, 175);
+        assert_eq!(beats, clicks);
+        assert_eq!(lows, clicks);
+    }
+
+    #[test]
+    fn min_beat_interval_suppresses_double_triggers() {
+        // 0.25 s at 50 fps is 13 frames: the click 5 frames later is an onset, not a beat.
+        let clicks = [50, 55, 80];
+        let (beats, lows) = detect(&clicks, 100);
+        assert_eq!(lows, clicks);
+        assert_eq!(beats, vec![50, 80]);
+    }
+}
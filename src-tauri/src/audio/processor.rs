@@ -0,0 +1,654 @@
+use super::{
+    amplitude_to_dbfs, recording, Agc, AudioAnalysisData, AudioChannel, BandRanges,
+    ChannelAnalysis, ChannelMelbanks, DspSettings, OnsetDetector, Resampler, SpectralAnalyzer,
+    SpectralFeatures, TempoTracker,
+};
+use crate::utils::delay::DelayQueue;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Platform-independent DSP chain shared by every capture backend.
+///
+/// Takes interleaved samples plus their sample rate and turns them into
+/// `AudioAnalysisData`: resampling, windowed FFT, filterbank,
+/// smoothing, AGC, onset detection and tempo tracking. Critical settings
+/// (FFT size, hop size, window, bands, filterbank, sample rate) are picked up
+/// automatically by rebuilding the internal state when they change.
+pub struct AudioProcessor {
+    settings: DspSettings,
+    source_sample_rate: u32,
+    fft_size: usize,
+    hop_size: usize,
+    frame_rate: f32,
+    band_ranges: BandRanges,
+    fft_plan: Arc<dyn Fft<f32>>,
+    fft_buffer: Vec<Complex<f32>>,
+    window: Vec<f32>,
+    filterbank: Vec<Vec<(usize, f32)>>,
+    /// Only present when `DspSettings::sample_rate` differs from the source rate.
+    resampler: Option<Resampler>,
+    audio_samples: Vec<f32>,
+    smoothed_melbanks: Vec<f32>,
+    full_scale: f32,
+    agc: Agc,
+    quiet_frames: u32,
+    onset_detector: OnsetDetector,
+    tempo_tracker: TempoTracker,
+    spectral_analyzer: Option<SpectralAnalyzer>,
+    channel_analysis: ChannelAnalysis,
+    channel_banks: Vec<ChannelBank>,
+}
+
+/// Resampler, FFT frame buffer, smoothing and AGC of one separately analysed channel.
+struct ChannelBank {
+    channel: AudioChannel,
+    resampler: Option<Resampler>,
+    audio_samples: Vec<f32>,
+    smoothed_melbanks: Vec<f32>,
+    agc: Agc,
+}
+
+impl ChannelBank {
+    /// Picks this bank's sample out of one interleaved frame.
+    fn sample(&self, frame: &[f32]) -> f32 {
+        let left = frame[0];
+        let right = frame.get(1).copied().unwrap_or(left);
+        match self.channel {
+            AudioChannel::Mix => frame.iter().sum::<f32>() / frame.len() as f32,
+            AudioChannel::Left => left,
+            AudioChannel::Right => right,
+            AudioChannel::Mid => (left + right) / 2.0,
+            AudioChannel::Side => (left - right) / 2.0,
+        }
+    }
+}
+
+impl AudioProcessor {
+    pub fn new(settings: &DspSettings, source_sample_rate: u32) -> Self {
+        let fft_size = settings.fft_size as usize;
+        let num_bands = settings.num_bands as usize;
+        let final_sample_rate = settings.sample_rate.unwrap_or(source_sample_rate);
+        let band_ranges = BandRanges::from_settings(settings);
+        let hop_size = match settings.hop_size {
+            0 => fft_size,
+            hop_size => (hop_size as usize).min(fft_size),
+        };
+        let frame_rate = final_sample_rate as f32 / hop_size as f32;
+
+        println!(
+            "[AUDIO] Initializing DSP. FFT size: {}, Hop: {}, Window: {:?}, Bands: {}, Freq Range: {}-{}Hz, Sample rate: {} -> {}",
+            fft_size, hop_size, settings.window_type, num_bands, settings.min_freq, settings.max_freq, source_sample_rate, final_sample_rate
+        );
+
+        let window = crate::utils::dsp::generate_window(fft_size, settings.window_type);
+        // Magnitude of a full-scale sine in its FFT bin: half the window's sum.
+        let full_scale = window.iter().sum::<f32>() / 2.0;
+
+        Self {
+            settings: settings.clone(),
+            source_sample_rate,
+            fft_size,
+            hop_size,
+            frame_rate,
+            band_ranges,
+            fft_plan: FftPlanner::new().plan_fft_forward(fft_size),
+            fft_buffer: vec![Complex::new(0.0, 0.0); fft_size],
+            window,
+            filterbank: crate::utils::dsp::generate_filterbank(
+                fft_size / 2,
+                final_sample_rate,
+                num_bands,
+                settings.min_freq,
+                settings.max_freq,
+                &settings.filterbank_type,
+            ),
+            resampler: Self::new_resampler(settings, source_sample_rate),
+            audio_samples: Vec::with_capacity(fft_size * 2),
+            smoothed_melbanks: vec![0.0; num_bands],
+            full_scale,
+            agc: Agc::new(num_bands, full_scale),
+            quiet_frames: 0,
+            onset_detector: OnsetDetector::new(num_bands, band_ranges, frame_rate),
+            tempo_tracker: TempoTracker::new(frame_rate),
+            spectral_analyzer: None,
+            channel_analysis: ChannelAnalysis::Off,
+            channel_banks: Vec::new(),
+        }
+    }
+
+    /// Creates the per-channel banks for `mode`. They start with as much (silent) history
+    /// as the mono mix has buffered, so their frames line up with the mono frames.
+    fn rebuild_channel_banks(&mut self, mode: ChannelAnalysis) {
+        let channels: &[AudioChannel] = match mode {
+            ChannelAnalysis::Off => &[],
+            ChannelAnalysis::Stereo => &[AudioChannel::Left, AudioChannel::Right],
+            ChannelAnalysis::StereoMidSide => &[
+                AudioChannel::Left,
+                AudioChannel::Right,
+                AudioChannel::Mid,
+                AudioChannel::Side,
+            ],
+        };
+        let num_bands = self.smoothed_melbanks.len();
+        self.channel_banks = channels
+            .iter()
+            .map(|&channel| ChannelBank {
+                channel,
+                resampler: Self::new_resampler(&self.settings, self.source_sample_rate),
+                audio_samples: vec![0.0; self.audio_samples.len()],
+                smoothed_melbanks: vec![0.0; num_bands],
+                agc: Agc::new(num_bands, self.full_scale),
+            })
+            .collect();
+        self.channel_analysis = mode;
+    }
+
+    fn new_resampler(settings: &DspSettings, source_sample_rate: u32) -> Option<Resampler> {
+        settings
+            .sample_rate
+            .filter(|&rate| rate != source_sample_rate)
+            .map(|rate| Resampler::new(settings.resampler_type, source_sample_rate, rate))
+    }
+
+    /// Sample rate the FFT runs at, after optional resampling.
+    pub fn analysis_sample_rate(&self) -> u32 {
+        self.settings.sample_rate.unwrap_or(self.source_sample_rate)
+    }
+
+    fn needs_rebuild(&self, settings: &DspSettings, source_sample_rate: u32) -> bool {
+        source_sample_rate != self.source_sample_rate
+            || settings.fft_size != self.settings.fft_size
+            || settings.num_bands != self.settings.num_bands
+            || settings.min_freq != self.settings.min_freq
+            || settings.max_freq != self.settings.max_freq
+            || settings.filterbank_type != self.settings.filterbank_type
+            || settings.sample_rate != self.settings.sample_rate
+            || settings.lows_max_freq != self.settings.lows_max_freq
+            || settings.mids_max_freq != self.settings.mids_max_freq
+            || settings.window_type != self.settings.window_type
+            || settings.hop_size != self.settings.hop_size
+            || settings.resampler_type != self.settings.resampler_type
+    }
+
+    /// Feeds interleaved samples through the DSP chain.
+    ///
+    /// Returns the analysis of the most recent complete FFT frame, or `None` if
+    /// no new frame (a full FFT window, advanced by the hop size) is ready yet.
+    /// Beats and onsets of any earlier frames in the same block are carried over.
+    pub fn process(
+        &mut self,
+        data: &[f32],
+        channels: usize,
+        source_sample_rate: u32,
+        settings: &DspSettings,
+    ) -> Option<AudioAnalysisData> {
+        if self.needs_rebuild(settings, source_sample_rate) {
+            *self = Self::new(settings, source_sample_rate);
+        }
+        let channels = channels.max(1);
+
+        if settings.channel_analysis != self.channel_analysis {
+            self.rebuild_channel_banks(settings.channel_analysis);
+        }
+
+        // Downmix first: the resamplers work on a single channel.
+        let mono_samples: Vec<f32> = data
+            .chunks(channels)
+            .map(|c| c.iter().sum::<f32>() / channels as f32)
+            .collect();
+        let mono_samples = match &mut self.resampler {
+            Some(resampler) => resampler.process(&mono_samples),
+            None => mono_samples,
+        };
+        self.audio_samples.extend(mono_samples);
+
+        for bank in &mut self.channel_banks {
+            let samples: Vec<f32> = data.chunks(channels).map(|c| bank.sample(c)).collect();
+            let samples = match &mut bank.resampler {
+                Some(resampler) => resampler.process(&samples),
+                None => samples,
+            };
+            bank.audio_samples.extend(samples);
+        }
+
+        let mut latest: Option<AudioAnalysisData> = None;
+        while self.audio_samples.len() >= self.fft_size {
+            let mut analysis = self.analyze_frame(settings);
+            if let Some(earlier) = &latest {
+                analysis.merge_events(earlier);
+            }
+            latest = Some(analysis);
+            self.audio_samples.drain(0..self.hop_size);
+        }
+        latest
+    }
+
+    fn analyze_frame(&mut self, settings: &DspSettings) -> AudioAnalysisData {
+        let fft_size = self.fft_size;
+        let frame = &self.audio_samples[..fft_size];
+        let rms = (frame.iter().map(|s| s * s).sum::<f32>() / fft_size as f32).sqrt();
+        let peak = frame.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        let dbfs = amplitude_to_dbfs(rms);
+        if dbfs < settings.noise_gate_db {
+            self.quiet_frames = self.quiet_frames.saturating_add(1);
+        } else {
+            self.quiet_frames = 0;
+        }
+        let silence_duration = self.quiet_frames as f32 / self.frame_rate;
+        let silence = self.quiet_frames > 0
+            && silence_duration * 1000.0 >= settings.noise_gate_hold_ms as f32;
+
+        let (magnitudes, mut raw_melbanks) = filterbank_energies(
+            self.fft_plan.as_ref(),
+            &mut self.fft_buffer,
+            &self.window,
+            &self.filterbank,
+            &self.audio_samples[..fft_size],
+        );
+        if silence {
+            // Let the bands decay to zero instead of having the AGC amplify the noise floor.
+            raw_melbanks.iter_mut().for_each(|band| *band = 0.0);
+        }
+        let onsets = self.onset_detector.process(&raw_melbanks);
+        let tempo = self.tempo_tracker.process(onsets.novelty);
+
+        for (smoothed, raw) in self.smoothed_melbanks.iter_mut().zip(&raw_melbanks) {
+            *smoothed =
+                (*smoothed * settings.smoothing_factor) + (raw * (1.0 - settings.smoothing_factor));
+        }
+        // While gated the AGC is held, so the music comes back at the level it left.
+        let melbanks = self.agc.process(
+            &self.smoothed_melbanks,
+            &settings.agc_strategy,
+            settings.agc_attack,
+            settings.agc_decay,
+            silence,
+        );
+
+        let spectral = if settings.spectral_features {
+            let sample_rate = self.analysis_sample_rate();
+            let analyzer = self.spectral_analyzer.get_or_insert_with(|| {
+                SpectralAnalyzer::new(
+                    fft_size / 2,
+                    sample_rate,
+                    settings.min_freq,
+                    settings.max_freq,
+                )
+            });
+            if silence {
+                analyzer.reset();
+                Some(SpectralFeatures {
+                    chroma: vec![0.0; 12],
+                    ..Default::default()
+                })
+            } else {
+                Some(analyzer.process(&magnitudes))
+            }
+        } else {
+            self.spectral_analyzer = None;
+            None
+        };
+
+        let channels = if self.channel_banks.is_empty() {
+            None
+        } else {
+            let mut channels = ChannelMelbanks::default();
+            for bank in &mut self.channel_banks {
+                let bands = if bank.audio_samples.len() >= fft_size {
+                    let (_, mut raw) = filterbank_energies(
+                        self.fft_plan.as_ref(),
+                        &mut self.fft_buffer,
+                        &self.window,
+                        &self.filterbank,
+                        &bank.audio_samples[..fft_size],
+                    );
+                    bank.audio_samples.drain(0..self.hop_size);
+                    if silence {
+                        raw.iter_mut().for_each(|band| *band = 0.0);
+                    }
+                    for (smoothed, raw) in bank.smoothed_melbanks.iter_mut().zip(&raw) {
+                        *smoothed = (*smoothed * settings.smoothing_factor)
+                            + (raw * (1.0 - settings.smoothing_factor));
+                    }
+                    bank.agc.process(
+                        &bank.smoothed_melbanks,
+                        &settings.agc_strategy,
+                        settings.agc_attack,
+                        settings.agc_decay,
+                        silence,
+                    )
+                } else {
+                    vec![0.0; bank.smoothed_melbanks.len()]
+                };
+                match bank.channel {
+                    AudioChannel::Left => channels.left = bands,
+                    AudioChannel::Right => channels.right = bands,
+                    AudioChannel::Mid => channels.mid = Some(bands),
+                    AudioChannel::Side => channels.side = Some(bands),
+                    AudioChannel::Mix => {}
+                }
+            }
+            Some(channels)
+        };
+
+        let mut analysis = AudioAnalysisData {
+            melbanks,
+            band_ranges: self.band_ranges,
+            rms,
+            peak,
+            dbfs,
+            agc_gain: self.agc.gain(),
+            silence,
+            silence_duration,
+            spectral,
+            channels,
+            ..Default::default()
+        };
+        analysis.apply_onsets(&onsets);
+        analysis.apply_tempo(&tempo);
+        analysis
+    }
+}
+
+/// Windows `samples`, runs the FFT and applies the filterbank.
+/// Returns the magnitude spectrum and the raw band energies.
+fn filterbank_energies(
+    fft_plan: &dyn Fft<f32>,
+    fft_buffer: &mut [Complex<f32>],
+    window: &[f32],
+    filterbank: &[Vec<(usize, f32)>],
+    samples: &[f32],
+) -> (Vec<f32>, Vec<f32>) {
+    for (i, sample) in samples.iter().enumerate() {
+        fft_buffer[i] = Complex::new(sample * window[i], 0.0);
+    }
+    fft_plan.process(fft_buffer);
+    let magnitudes: Vec<f32> = fft_buffer[0..samples.len() / 2]
+        .iter()
+        .map(|c| c.norm_sqr().sqrt())
+        .collect();
+    let bands = filterbank
+        .iter()
+        .map(|filter| {
+            filter
+                .iter()
+                .map(|&(bin_index, weight)| magnitudes[bin_index] * weight)
+                .sum::<f32>()
+        })
+        .collect();
+    (magnitudes, bands)
+}
+
+/// Runs an `AudioProcessor` against the live DSP settings and publishes every
+/// finished frame to the shared analysis data, a positive `audio_delay_ms` after
+/// it was captured.
+pub struct AnalysisSink {
+    processor: Option<AudioProcessor>,
+    delay_queue: DelayQueue<AudioAnalysisData>,
+    audio_data: Arc<Mutex<AudioAnalysisData>>,
+    dsp_settings: Arc<Mutex<DspSettings>>,
+}
+
+impl AnalysisSink {
+    pub fn new(
+        audio_data: Arc<Mutex<AudioAnalysisData>>,
+        dsp_settings: Arc<Mutex<DspSettings>>,
+    ) -> Self {
+        Self {
+            processor: None,
+            delay_queue: DelayQueue::new(),
+            audio_data,
+            dsp_settings,
+        }
+    }
+
+    /// Pushes samples that were captured just now.
+    pub fn push(&mut self, samples: &[f32], channels: usize, sample_rate: u32) {
+        self.push_captured(samples, channels, sample_rate, Instant::now());
+    }
+
+    /// Pushes samples the device captured at `captured`, which may be earlier than
+    /// now when the backend buffers input.
+    pub fn push_captured(
+        &mut self,
+        samples: &[f32],
+        channels: usize,
+        sample_rate: u32,
+        captured: Instant,
+    ) {
+        let settings = self.dsp_settings.lock().unwrap().clone();
+        let processor = self
+            .processor
+            .get_or_insert_with(|| AudioProcessor::new(&settings, sample_rate));
+        let now = Instant::now();
+        recording::record_input(&self.audio_data, samples, channels, sample_rate);
+        if let Some(analysis) = processor.process(samples, channels, sample_rate, &settings) {
+            self.delay_queue.push(captured, analysis);
+        }
+        // Negative delays hold back the LED output in the engine instead.
+        let delay = Duration::from_millis(settings.audio_delay_ms.max(0) as u64);
+        let released = self
+            .delay_queue
+            .pop_merged(now, delay, |newer, earlier| newer.merge_events(&earlier));
+        if let Some(mut analysis) = released {
+            // Only queued here; the recording's writer thread does the encoding and I/O.
+            recording::record_analysis(&self.audio_data, &analysis);
+            if let Ok(mut data) = self.audio_data.lock() {
+                analysis.sequence = data.sequence.wrapping_add(1);
+                *data = analysis;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::ResamplerType;
+    use crate::utils::dsp::{center_frequencies, FilterbankType};
+    use std::f32::consts::PI;
+
+    fn test_settings() -> DspSettings {
+        DspSettings {
+            fft_size: 1024,
+            sample_rate: None,
+            smoothing_factor: 0.0,
+            ..Default::default()
+        }
+    }
+
+    fn sine(freq: f32, sample_rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (2.0 * PI * freq * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn waits_for_a_full_frame() {
+        let settings = test_settings();
+        let mut processor = AudioProcessor::new(&settings, 44100);
+        assert!(processor
+            .process(&[0.0; 1000], 1, 44100, &settings)
+            .is_none());
+        assert!(processor.process(&[0.0; 24], 1, 44100, &settings).is_some());
+    }
+
+    #[test]
+    fn overlapping_hops_emit_a_frame_per_hop() {
+        let settings = DspSettings {
+            hop_size: 256,
+            ..test_settings()
+        };
+        let mut processor = AudioProcessor::new(&settings, 44100);
+        assert!(processor
+            .process(&[0.0; 1024], 1, 44100, &settings)
+            .is_some());
+        assert!(processor
+            .process(&[0.0; 255], 1, 44100, &settings)
+            .is_none());
+        assert!(processor.process(&[0.0; 1], 1, 44100, &settings).is_some());
+    }
+
+    #[test]
+    fn does_not_overlap_frames_by_default() {
+        let settings = DspSettings {
+            fft_size: 2048,
+            ..test_settings()
+        };
+        assert_eq!(settings.hop_size, 0);
+        let mut processor = AudioProcessor::new(&settings, 44100);
+        assert!(processor
+            .process(&[0.0; 2048], 1, 44100, &settings)
+            .is_some());
+        assert!(processor
+            .process(&[0.0; 2047], 1, 44100, &settings)
+            .is_none());
+        assert!(processor.process(&[0.0; 1], 1, 44100, &settings).is_some());
+    }
+
+    #[test]
+    fn silence_produces_empty_bands() {
+        let settings = test_settings();
+        let mut processor = AudioProcessor::new(&settings, 44100);
+        let analysis = processor
+            .process(&[0.0; 4096], 2, 44100, &settings)
+            .unwrap();
+        assert_eq!(analysis.melbanks.len(), 24);
+        assert!(analysis.melbanks.iter().all(|&v| v == 0.0));
+        assert!(!analysis.beat && !analysis.onset);
+    }
+
+    #[test]
+    fn sine_peaks_in_matching_band() {
+        let settings = DspSettings {
+            agc_attack: 1.0,
+            ..test_settings()
+        };
+        let mut processor = AudioProcessor::new(&settings, 44100);
+        let analysis = processor
+            .process(&sine(1000.0, 44100, 8192), 1, 44100, &settings)
+            .unwrap();
+        let centers = center_frequencies(
+            24,
+            settings.min_freq,
+            settings.max_freq,
+            &FilterbankType::Blade,
+        );
+        let loudest = analysis
+            .melbanks
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, _)| i)
+            .unwrap();
+        let closest = centers
+            .iter()
+            .enumerate()
+            .min_by(|a, b| (a.1 - 1000.0).abs().total_cmp(&(b.1 - 1000.0).abs()))
+            .map(|(i, _)| i)
+            .unwrap();
+        assert!(
+            loudest.abs_diff(closest) <= 1,
+            "band {} vs {}",
+            loudest,
+            closest
+        );
+        assert_eq!(analysis.band_ranges, BandRanges::from_settings(&settings));
+    }
+
+    #[test]
+    fn downmixes_interleaved_channels() {
+        let settings = test_settings();
+        let mut processor = AudioProcessor::new(&settings, 44100);
+        // Left and right cancel out, so the mono mix is silent.
+        let stereo: Vec<f32> = sine(440.0, 44100, 2048)
+            .into_iter()
+            .flat_map(|s| [s, -s])
+            .collect();
+        let analysis = processor.process(&stereo, 2, 44100, &settings).unwrap();
+        assert!(analysis.melbanks.iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn downmixes_before_resampling() {
+        for resampler_type in [ResamplerType::Linear, ResamplerType::Sinc] {
+            let settings = DspSettings {
+                sample_rate: Some(30000),
+                resampler_type,
+                ..test_settings()
+            };
+            let mut processor = AudioProcessor::new(&settings, 48000);
+            let stereo: Vec<f32> = sine(440.0, 48000, 4096)
+                .into_iter()
+                .flat_map(|s| [s, -s])
+                .collect();
+            let analysis = processor.process(&stereo, 2, 48000, &settings).unwrap();
+            assert!(analysis.melbanks.iter().all(|&v| v == 0.0));
+        }
+    }
+
+    #[test]
+    fn analyses_stereo_channels_separately() {
+        let settings = DspSettings {
+            channel_analysis: ChannelAnalysis::StereoMidSide,
+            agc_attack: 1.0,
+            ..test_settings()
+        };
+        let mut processor = AudioProcessor::new(&settings, 44100);
+        // Sine on the left only: right is silent, mid and side each carry half of it.
+        let stereo: Vec<f32> = sine(1000.0, 44100, 2048)
+            .into_iter()
+            .flat_map(|s| [s, 0.0])
+            .collect();
+        let analysis = processor.process(&stereo, 2, 44100, &settings).unwrap();
+        let channels = analysis.channels.as_ref().unwrap();
+        assert!(channels.left.iter().any(|&v| v > 0.0));
+        assert!(channels.right.iter().all(|&v| v == 0.0));
+        assert!(channels.mid.as_ref().unwrap().iter().any(|&v| v > 0.0));
+        assert!(channels.side.as_ref().unwrap().iter().any(|&v| v > 0.0));
+        assert_eq!(
+            analysis.channel_melbanks(AudioChannel::Right),
+            &channels.right[..]
+        );
+
+        let mono = processor
+            .process(&stereo, 2, 44100, &test_settings())
+            .unwrap();
+        assert!(mono.channels.is_none());
+        assert_eq!(
+            mono.channel_melbanks(AudioChannel::Left),
+            &mono.melbanks[..]
+        );
+    }
+
+    #[test]
+    fn reports_a_beat_from_any_frame_of_a_large_block() {
+        let settings = DspSettings {
+            hop_size: 256,
+            ..test_settings()
+        };
+        let mut processor = AudioProcessor::new(&settings, 44100);
+        // Let the onset detector settle on silence, then feed a click followed by
+        // enough silence that the last frame of the block no longer contains it.
+        processor.process(&[0.0; 44100], 1, 44100, &settings);
+        let mut block = vec![0.0f32; 8192];
+        block[1024..1536].iter_mut().for_each(|s| *s = 1.0);
+        let analysis = processor.process(&block, 1, 44100, &settings).unwrap();
+        assert!(analysis.beat);
+        assert!(analysis.onset_lows);
+        assert!(analysis.beat_strength > 0.0);
+    }
+
+    #[test]
+    fn rebuilds_when_critical_settings_change() {
+        let mut settings = test_settings();
+        let mut processor = AudioProcessor::new(&settings, 44100);
+        settings.num_bands = 48;
+        let analysis = processor
+            .process(&sine(440.0, 44100, 1024), 1, 44100, &settings)
+            .unwrap();
+        assert_eq!(analysis.melbanks.len(), 48);
+    }
+}
@@ -0,0 +1,223 @@
+//! PulseAudio / PipeWire monitor sources, offered as "System Audio (...)" loopback devices on Linux.
+//!
+//! cpal only sees ALSA devices and can't pick a source on the `pulse` plugin, so a monitor
+//! is recorded with `parec`, which names its source per stream. PipeWire serves the same
+//! protocol via pipewire-pulse.
+use super::sources::{should_stop, SourceThread};
+use super::{AnalysisSink, AudioAnalysisData, DspSettings};
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+
+pub const LOOPBACK_PREFIX: &str = "System Audio (";
+const MONITOR_SAMPLE_RATE: u32 = 48000;
+const MONITOR_CHANNELS: usize = 2;
+const CHUNK_FRAMES: usize = 480;
+
+#[derive(Debug, Clone)]
+pub struct MonitorSource {
+    pub source_name: String,
+    pub sink_name: String,
+    pub description: String,
+}
+
+impl MonitorSource {
+    pub fn loopback_name(&self) -> String {
+        format!("{}{})", LOOPBACK_PREFIX, self.description)
+    }
+}
+
+fn pactl(args: &[&str]) -> Option<String> {
+    let output = Command::new("pactl")
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// All monitor sources, i.e. one per output sink.
+pub fn list_monitor_sources() -> Vec<MonitorSource> {
+    pactl(&["list", "sources"])
+        .map(|text| parse_monitor_sources(&text))
+        .unwrap_or_default()
+}
+
+fn parse_monitor_sources(text: &str) -> Vec<MonitorSource> {
+    let mut monitors = Vec::new();
+    for block in text.split("Source #").skip(1) {
+        let field = |key: &str| {
+            block
+                .lines()
+                .find_map(|line| line.trim().strip_prefix(key))
+                .map(|value| value.trim().to_string())
+        };
+        let (Some(source_name), Some(sink_name)) = (field("Name:"), field("Monitor of Sink:"))
+        else {
+            continue;
+        };
+        if sink_name == "n/a" {
+            continue;
+        }
+        let description = field("Description:")
+            .map(|d| {
+                d.strip_prefix("Monitor of ")
+                    .map(str::to_string)
+                    .unwrap_or(d)
+            })
+            .unwrap_or_else(|| sink_name.clone());
+        monitors.push(MonitorSource {
+            source_name,
+            sink_name,
+            description,
+        });
+    }
+    monitors
+}
+
+pub fn default_sink_name() -> Option<String> {
+    pactl(&["info"])?
+        .lines()
+        .find_map(|line| line.strip_prefix("Default Sink:"))
+        .map(|name| name.trim().to_string())
+}
+
+pub fn find_monitor(loopback_name: &str) -> Option<MonitorSource> {
+    list_monitor_sources()
+        .into_iter()
+        .find(|monitor| monitor.loopback_name() == loopback_name)
+}
+
+/// Records a monitor source through a `parec` child process.
+///
+/// The process is killed and the reader thread joined when this handle is dropped.
+pub struct MonitorCapture {
+    child: Child,
+    _thread: SourceThread,
+}
+
+impl MonitorCapture {
+    pub fn start(
+        monitor: &MonitorSource,
+        audio_data: Arc<Mutex<AudioAnalysisData>>,
+        dsp_settings: Arc<Mutex<DspSettings>>,
+        error_tx: mpsc::Sender<String>,
+    ) -> Result<Self, String> {
+        let mut child = Command::new("parec")
+            .arg(format!("--device={}", monitor.source_name))
+            .arg("--format=float32le")
+            .arg(format!("--rate={}", MONITOR_SAMPLE_RATE))
+            .arg(format!("--channels={}", MONITOR_CHANNELS))
+            .arg("--raw")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Could not start parec: {}", e))?;
+        let Some(mut stdout) = child.stdout.take() else {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("parec has no output".to_string());
+        };
+        let thread = SourceThread::spawn("audio-monitor", move |stop| {
+            let mut sink = AnalysisSink::new(audio_data, dsp_settings);
+            let mut bytes = vec![0u8; CHUNK_FRAMES * MONITOR_CHANNELS * 4];
+            while !should_stop(&stop) {
+                if let Err(e) = stdout.read_exact(&mut bytes) {
+                    if !should_stop(&stop) {
+                        let _ = error_tx.send(format!("Monitor recording stopped: {}", e));
+                    }
+                    return;
+                }
+                let samples: Vec<f32> = bytes
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect();
+                sink.push(&samples, MONITOR_CHANNELS, MONITOR_SAMPLE_RATE);
+            }
+        });
+        match thread {
+            Ok(thread) => Ok(Self {
+                child,
+                _thread: thread,
+            }),
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Drop for MonitorCapture {
+    fn drop(&mut self) {
+        // Closes the pipe, so the reader thread returns before it is joined.
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACTL_SOURCES: &str = "\
+Source #0
+\tState: SUSPENDED
+\tName: alsa_output.pci-0000_00_1f.3.analog-stereo.monitor
+\tDescription: Monitor of Built-in Audio Analog Stereo
+\tDriver: module-alsa-card.c
+\tMonitor of Sink: alsa_output.pci-0000_00_1f.3.analog-stereo
+\tLatency: 0 usec, configured 0 usec
+
+Source #1
+\tState: RUNNING
+\tName: alsa_input.pci-0000_00_1f.3.analog-stereo
+\tDescription: Built-in Audio Analog Stereo
+\tMonitor of Sink: n/a
+
+Source #2
+\tState: IDLE
+\tName: bluez_output.00_11_22_33_44_55.1.monitor
+\tMonitor of Sink: bluez_output.00_11_22_33_44_55.1
+";
+
+    #[test]
+    fn lists_only_monitor_sources() {
+        let monitors = parse_monitor_sources(PACTL_SOURCES);
+        assert_eq!(monitors.len(), 2);
+        assert_eq!(
+            monitors[0].source_name,
+            "alsa_output.pci-0000_00_1f.3.analog-stereo.monitor"
+        );
+        assert_eq!(
+            monitors[0].sink_name,
+            "alsa_output.pci-0000_00_1f.3.analog-stereo"
+        );
+        assert_eq!(monitors[1].sink_name, "bluez_output.00_11_22_33_44_55.1");
+    }
+
+    #[test]
+    fn names_monitors_after_their_sink() {
+        let monitors = parse_monitor_sources(PACTL_SOURCES);
+        assert_eq!(
+            monitors[0].loopback_name(),
+            "System Audio (Built-in Audio Analog Stereo)"
+        );
+        // Without a description the sink name is shown.
+        assert_eq!(
+            monitors[1].loopback_name(),
+            "System Audio (bluez_output.00_11_22_33_44_55.1)"
+        );
+    }
+
+    #[test]
+    fn ignores_output_without_sources() {
+        assert!(parse_monitor_sources("").is_empty());
+        assert!(parse_monitor_sources("Connection failure: Connection refused\n").is_empty());
+    }
+}
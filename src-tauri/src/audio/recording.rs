@@ -0,0 +1,382 @@
+use super::{AudioAnalysisData, SharedAudioData};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+/// The running recording, if any. Only the sink publishing into the recorded
+/// `SharedAudioData` sends data to it.
+static RECORDING: Lazy<Mutex<Option<Recording>>> = Lazy::new(Default::default);
+
+const WAV_HEADER_LEN: u32 = 44;
+/// Blocks of input and analysis frames waiting for the writer thread, about 1-2 s of audio.
+const RECORDING_QUEUE_LEN: usize = 256;
+
+#[derive(Serialize, Type, Clone)]
+pub struct AudioRecordingInfo {
+    pub wav_path: String,
+    pub analysis_path: String,
+    /// Seconds of raw input written to the WAV file.
+    pub duration_secs: f32,
+    pub analysis_frames: u32,
+}
+
+/// One line of an analysis recording: a published frame and the time it was
+/// published at, in seconds since the recording started.
+#[derive(Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub time: f64,
+    pub analysis: AudioAnalysisData,
+}
+
+#[derive(Serialize)]
+struct RecordedFrameRef<'a> {
+    time: f64,
+    analysis: &'a AudioAnalysisData,
+}
+
+/// 16-bit PCM WAV file whose sizes are filled in when it is finished.
+struct WavWriter {
+    file: BufWriter<File>,
+    channels: u16,
+    sample_rate: u32,
+    data_bytes: u32,
+}
+
+impl WavWriter {
+    fn create(path: &Path, channels: u16, sample_rate: u32) -> io::Result<Self> {
+        let mut writer = Self {
+            file: BufWriter::new(File::create(path)?),
+            channels,
+            sample_rate,
+            data_bytes: 0,
+        };
+        writer.write_header()?;
+        Ok(writer)
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let block_align = self.channels * 2;
+        let f = &mut self.file;
+        f.write_all(b"RIFF")?;
+        f.write_all(&(WAV_HEADER_LEN - 8 + self.data_bytes).to_le_bytes())?;
+        f.write_all(b"WAVEfmt ")?;
+        f.write_all(&16u32.to_le_bytes())?;
+        f.write_all(&1u16.to_le_bytes())?;
+        f.write_all(&self.channels.to_le_bytes())?;
+        f.write_all(&self.sample_rate.to_le_bytes())?;
+        f.write_all(&(self.sample_rate * block_align as u32).to_le_bytes())?;
+        f.write_all(&block_align.to_le_bytes())?;
+        f.write_all(&16u16.to_le_bytes())?;
+        f.write_all(b"data")?;
+        f.write_all(&self.data_bytes.to_le_bytes())
+    }
+
+    fn write(&mut self, samples: &[f32]) -> io::Result<()> {
+        self.data_bytes = (samples.len() as u32)
+            .checked_mul(2)
+            .and_then(|len| self.data_bytes.checked_add(len))
+            .filter(|&len| len <= u32::MAX - WAV_HEADER_LEN)
+            .ok_or_else(|| io::Error::other("WAV size limit reached"))?;
+        for sample in samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.file.write_all(&value.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn duration_secs(&self) -> f32 {
+        let frames = self.data_bytes / (self.channels as u32 * 2);
+        frames as f32 / self.sample_rate as f32
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(0))?;
+        self.write_header()?;
+        self.file.flush()
+    }
+}
+
+/// Data handed from the analysis sink to the writer thread.
+enum RecordedData {
+    Input {
+        samples: Vec<f32>,
+        channels: usize,
+        sample_rate: u32,
+    },
+    Analysis {
+        time: f64,
+        analysis: Box<AudioAnalysisData>,
+    },
+}
+
+/// Owned by the writer thread: encodes and writes everything the sink sends.
+struct RecordingWriter {
+    wav_path: PathBuf,
+    /// Created on the first input, once the format is known.
+    wav: Option<WavWriter>,
+    format_warned: bool,
+    analysis: BufWriter<File>,
+    info: Arc<Mutex<AudioRecordingInfo>>,
+}
+
+impl RecordingWriter {
+    fn write(&mut self, data: RecordedData) -> io::Result<()> {
+        match data {
+            RecordedData::Input {
+                samples,
+                channels,
+                sample_rate,
+            } => self.write_input(&samples, channels, sample_rate),
+            RecordedData::Analysis { time, analysis } => self.write_analysis(time, &analysis),
+        }
+    }
+
+    fn write_input(
+        &mut self,
+        samples: &[f32],
+        channels: usize,
+        sample_rate: u32,
+    ) -> io::Result<()> {
+        let wav = match &mut self.wav {
+            Some(wav) => wav,
+            None => self.wav.insert(WavWriter::create(
+                &self.wav_path,
+                channels as u16,
+                sample_rate,
+            )?),
+        };
+        if wav.channels as usize != channels || wav.sample_rate != sample_rate {
+            // A device change mid-recording; WAV has no way to switch formats.
+            if !self.format_warned {
+                eprintln!(
+                    "[AUDIO] Input format changed to {} Hz, {} channels; not recording raw input until it changes back.",
+                    sample_rate, channels
+                );
+                self.format_warned = true;
+            }
+            return Ok(());
+        }
+        wav.write(samples)?;
+        self.info.lock().unwrap().duration_secs = wav.duration_secs();
+        Ok(())
+    }
+
+    fn write_analysis(&mut self, time: f64, analysis: &AudioAnalysisData) -> io::Result<()> {
+        serde_json::to_writer(&mut self.analysis, &RecordedFrameRef { time, analysis })?;
+        self.analysis.write_all(b"\n")?;
+        self.info.lock().unwrap().analysis_frames += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.analysis.flush()?;
+        match &mut self.wav {
+            Some(wav) => wav.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes until the recording is stopped or a write fails, then finishes the files.
+    fn run(mut self, data: Receiver<RecordedData>) -> io::Result<()> {
+        let result = data.iter().try_for_each(|data| self.write(data));
+        if let Err(e) = &result {
+            eprintln!("[AUDIO] Recording failed, stopping it: {}", e);
+        }
+        result.and(self.finish())
+    }
+}
+
+/// The sink side of a running recording. Dropping `data` ends the writer thread.
+struct Recording {
+    audio_data: Arc<Mutex<AudioAnalysisData>>,
+    started: Instant,
+    data: SyncSender<RecordedData>,
+    overflow_logged: bool,
+    info: Arc<Mutex<AudioRecordingInfo>>,
+    writer: JoinHandle<io::Result<()>>,
+}
+
+impl Recording {
+    fn info(&self) -> AudioRecordingInfo {
+        self.info.lock().unwrap().clone()
+    }
+
+    /// Waits for the writer to finish the files.
+    fn finish(self) -> Result<AudioRecordingInfo, String> {
+        drop(self.data);
+        self.writer
+            .join()
+            .map_err(|_| "Recording writer panicked".to_string())?
+            .map_err(|e| e.to_string())?;
+        let info = self.info.lock().unwrap().clone();
+        Ok(info)
+    }
+}
+
+/// Hands data to the recording if it belongs to `audio_data`, without blocking.
+/// Data the writer can't keep up with is dropped.
+fn send_to_recording(
+    audio_data: &Arc<Mutex<AudioAnalysisData>>,
+    data: impl FnOnce(&Recording) -> RecordedData,
+) {
+    let mut recording = RECORDING.lock().unwrap();
+    let Some(active) = recording
+        .as_mut()
+        .filter(|r| Arc::ptr_eq(&r.audio_data, audio_data))
+    else {
+        return;
+    };
+    match active.data.try_send(data(active)) {
+        Ok(()) => {}
+        Err(TrySendError::Full(_)) => {
+            if !active.overflow_logged {
+                eprintln!("[AUDIO] Recording can't keep up, dropping data.");
+                active.overflow_logged = true;
+            }
+        }
+        Err(TrySendError::Disconnected(_)) => {
+            // The writer failed and has already logged why.
+            if let Some(failed) = recording.take() {
+                let _ = failed.finish();
+            }
+        }
+    }
+}
+
+/// Called by the analysis sink with every block of raw, interleaved input.
+pub fn record_input(
+    audio_data: &Arc<Mutex<AudioAnalysisData>>,
+    samples: &[f32],
+    channels: usize,
+    sample_rate: u32,
+) {
+    send_to_recording(audio_data, |_| RecordedData::Input {
+        samples: samples.to_vec(),
+        channels,
+        sample_rate,
+    });
+}
+
+/// Called by the analysis sink with every frame it publishes.
+pub fn record_analysis(audio_data: &Arc<Mutex<AudioAnalysisData>>, analysis: &AudioAnalysisData) {
+    send_to_recording(audio_data, |r| RecordedData::Analysis {
+        time: r.started.elapsed().as_secs_f64(),
+        analysis: Box::new(analysis.clone()),
+    });
+}
+
+/// Reads an analysis recording written by `start_audio_recording`.
+pub fn read_analysis_file(path: &Path) -> Result<Vec<RecordedFrame>, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut frames = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("{}: {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let frame = serde_json::from_str(&line)
+            .map_err(|e| format!("{}, line {}: {}", path.display(), i + 1, e))?;
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// Starts recording the input and analysis published into `audio_data`.
+pub fn start(
+    directory: &Path,
+    audio_data: Arc<Mutex<AudioAnalysisData>>,
+) -> Result<AudioRecordingInfo, String> {
+    let mut recording = RECORDING.lock().map_err(|e| e.to_string())?;
+    if recording.is_some() {
+        return Err("A recording is already running".to_string());
+    }
+    fs::create_dir_all(directory).map_err(|e| format!("{}: {}", directory.display(), e))?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let wav_path = directory.join(format!("recording-{}.wav", timestamp));
+    let analysis_path = directory.join(format!("recording-{}.analysis.jsonl", timestamp));
+    let analysis =
+        File::create(&analysis_path).map_err(|e| format!("{}: {}", analysis_path.display(), e))?;
+
+    let info = Arc::new(Mutex::new(AudioRecordingInfo {
+        wav_path: wav_path.to_string_lossy().into_owned(),
+        analysis_path: analysis_path.to_string_lossy().into_owned(),
+        duration_secs: 0.0,
+        analysis_frames: 0,
+    }));
+    let writer = RecordingWriter {
+        wav_path,
+        wav: None,
+        format_warned: false,
+        analysis: BufWriter::new(analysis),
+        info: info.clone(),
+    };
+    let (data, data_rx) = mpsc::sync_channel(RECORDING_QUEUE_LEN);
+    let writer = thread::Builder::new()
+        .name("audio-recording".to_string())
+        .spawn(move || writer.run(data_rx))
+        .map_err(|e| e.to_string())?;
+
+    let active = Recording {
+        audio_data,
+        started: Instant::now(),
+        data,
+        overflow_logged: false,
+        info,
+        writer,
+    };
+    let info = active.info();
+    println!(
+        "[AUDIO] Recording input to {} and analysis to {}",
+        info.wav_path, info.analysis_path
+    );
+    *recording = Some(active);
+    Ok(info)
+}
+
+/// Stops the running recording once everything sent so far is written.
+pub fn stop() -> Result<AudioRecordingInfo, String> {
+    let active = RECORDING
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take()
+        .ok_or_else(|| "No recording is running".to_string())?;
+    let info = active.finish()?;
+    println!(
+        "[AUDIO] Recording stopped after {:.1} s and {} analysis frames.",
+        info.duration_secs, info.analysis_frames
+    );
+    Ok(info)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn start_audio_recording(
+    directory: String,
+    audio_data: State<SharedAudioData>,
+) -> Result<AudioRecordingInfo, String> {
+    start(Path::new(&directory), audio_data.0.clone())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn stop_audio_recording() -> Result<AudioRecordingInfo, String> {
+    stop()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_audio_recording() -> Result<Option<AudioRecordingInfo>, String> {
+    let recording = RECORDING.lock().map_err(|e| e.to_string())?;
+    Ok(recording.as_ref().map(Recording::info))
+}
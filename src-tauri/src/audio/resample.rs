@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::f64::consts::PI;
+
+// Zero crossings of the sinc kernel on each side of the output sample.
+const SINC_ZERO_CROSSINGS: usize = 16;
+// Kernel table entries per input sample of distance.
+const SINC_TABLE_RESOLUTION: usize = 512;
+// Fraction of the lower Nyquist frequency kept by the anti-aliasing filter.
+const SINC_CUTOFF: f64 = 0.95;
+
+/// Interpolation used when `DspSettings::sample_rate` forces a different analysis rate.
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ResamplerType {
+    /// Cheap, but folds everything above the new Nyquist frequency back into the spectrum.
+    #[default]
+    Linear,
+    /// Windowed sinc with a low-pass at the lower of the two Nyquist frequencies.
+    Sinc,
+}
+
+/// Streaming mono resampler. Keeps its input history and read position between
+/// calls, so audio split across callbacks resamples the same as in one piece.
+pub struct Resampler {
+    resampler_type: ResamplerType,
+    source_rate: u32,
+    target_rate: u32,
+    /// Half the kernel length in input samples (0 for linear).
+    radius: usize,
+    /// `kernel(d)` for d = i / SINC_TABLE_RESOLUTION, already scaled by the cutoff.
+    sinc_table: Vec<f32>,
+    /// Input samples still needed for upcoming outputs.
+    buffer: Vec<f32>,
+    /// Read position of the next output sample: `buffer[index]` plus
+    /// `phase / target_rate` of a sample. Kept as integers so it never drifts.
+    index: usize,
+    phase: u32,
+}
+
+impl Resampler {
+    pub fn new(resampler_type: ResamplerType, source_rate: u32, target_rate: u32) -> Self {
+        let step = source_rate as f64 / target_rate as f64;
+        let (radius, sinc_table) = match resampler_type {
+            ResamplerType::Linear => (0, Vec::new()),
+            ResamplerType::Sinc => {
+                // When downsampling, the kernel is stretched so its cutoff lands below
+                // the target's Nyquist frequency.
+                let cutoff = SINC_CUTOFF * (1.0 / step).min(1.0);
+                let radius = (SINC_ZERO_CROSSINGS as f64 / cutoff).ceil() as usize;
+                (radius, sinc_table(cutoff, radius))
+            }
+        };
+        Self {
+            resampler_type,
+            source_rate,
+            target_rate,
+            radius,
+            sinc_table,
+            // Silent history, so the first output lines up with the first input sample.
+            buffer: vec![0.0; radius],
+            index: radius,
+            phase: 0,
+        }
+    }
+
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        self.buffer.extend_from_slice(input);
+        let capacity = input.len() as u64 * self.target_rate as u64 / self.source_rate as u64;
+        let mut output = Vec::with_capacity(capacity as usize + 1);
+        while self.index + self.radius.max(1) < self.buffer.len() {
+            let fraction = self.phase as f64 / self.target_rate as f64;
+            output.push(match self.resampler_type {
+                ResamplerType::Linear => {
+                    let (a, b) = (self.buffer[self.index], self.buffer[self.index + 1]);
+                    a + (b - a) * fraction as f32
+                }
+                ResamplerType::Sinc => self.sinc_sample(self.index, fraction),
+            });
+            self.phase += self.source_rate;
+            self.index += (self.phase / self.target_rate) as usize;
+            self.phase %= self.target_rate;
+        }
+
+        let consumed = self
+            .index
+            .saturating_sub(self.radius)
+            .min(self.buffer.len());
+        self.buffer.drain(..consumed);
+        self.index -= consumed;
+        output
+    }
+
+    fn sinc_sample(&self, index: usize, fraction: f64) -> f32 {
+        let first = index + 1 - self.radius;
+        let mut sum = 0.0;
+        let mut weight_sum = 0.0;
+        for (k, &sample) in self.buffer[first..=index + self.radius].iter().enumerate() {
+            let distance = ((first + k) as f64 - index as f64 - fraction).abs();
+            let weight = self.kernel(distance);
+            sum += sample * weight;
+            weight_sum += weight;
+        }
+        // Normalizing keeps the DC gain at exactly 1 for every fractional position.
+        if weight_sum.abs() > f32::EPSILON {
+            sum / weight_sum
+        } else {
+            0.0
+        }
+    }
+
+    fn kernel(&self, distance: f64) -> f32 {
+        let scaled = distance * SINC_TABLE_RESOLUTION as f64;
+        let i = scaled as usize;
+        if i + 1 >= self.sinc_table.len() {
+            return 0.0;
+        }
+        let t = (scaled - i as f64) as f32;
+        self.sinc_table[i] + (self.sinc_table[i + 1] - self.sinc_table[i]) * t
+    }
+}
+
+/// Blackman-windowed sinc, sampled from 0 to `radius` input samples of distance.
+fn sinc_table(cutoff: f64, radius: usize) -> Vec<f32> {
+    let len = radius * SINC_TABLE_RESOLUTION + 2;
+    (0..len)
+        .map(|i| {
+            let distance = i as f64 / SINC_TABLE_RESOLUTION as f64;
+            if distance >= radius as f64 {
+                return 0.0;
+            }
+            let x = PI * cutoff * distance;
+            let sinc = if x == 0.0 { 1.0 } else { x.sin() / x };
+            let w = PI * (distance / radius as f64 + 1.0);
+            let window = 0.42 - 0.5 * w.cos() + 0.08 * (2.0 * w).cos();
+            (cutoff * sinc * window) as f32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exponential sweep from `from` to `to` Hz over `len` samples.
+    fn sweep(from: f64, to: f64, sample_rate: u32, len: usize) -> Vec<f32> {
+        let duration = len as f64 / sample_rate as f64;
+        let k = (to / from).ln() / duration;
+        (0..len)
+            .map(|i| {
+                let t = i as f64 / sample_rate as f64;
+                (2.0 * PI * from * ((k * t).exp() - 1.0) / k).sin() as f32
+            })
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    /// Resamples a 48 kHz sweep to 16 kHz in callback-sized chunks and returns the
+    /// output RMS while the sweep is in the passband (1-6 kHz) and above the new
+    /// Nyquist frequency (9-20 kHz), where anything left is aliasing.
+    fn sweep_levels(resampler_type: ResamplerType) -> (f32, f32) {
+        let (from, to, len) = (100.0, 22000.0, 48000 * 4);
+        let input = sweep(from, to, 48000, len);
+        let mut resampler = Resampler::new(resampler_type, 48000, 16000);
+        let output: Vec<f32> = input
+            .chunks(480)
+            .flat_map(|chunk| resampler.process(chunk))
+            .collect();
+
+        // Output index at which the sweep passes `hz`.
+        let duration = len as f64 / 48000.0;
+        let at = |hz: f64| ((hz / from).ln() / (to / from).ln() * duration * 16000.0) as usize;
+        (
+            rms(&output[at(1000.0)..at(6000.0)]),
+            rms(&output[at(9000.0)..at(20000.0)]),
+        )
+    }
+
+    #[test]
+    fn sinc_suppresses_aliasing_of_a_sweep() {
+        let (passband, aliasing) = sweep_levels(ResamplerType::Sinc);
+        assert!(passband > 0.65, "passband rms {}", passband);
+        assert!(aliasing < 0.001, "aliasing rms {}", aliasing);
+
+        let (_, linear_aliasing) = sweep_levels(ResamplerType::Linear);
+        assert!(
+            linear_aliasing > 0.5,
+            "linear aliasing rms {}",
+            linear_aliasing
+        );
+    }
+
+    #[test]
+    fn chunked_processing_matches_one_pass() {
+        let input = sweep(100.0, 10000.0, 44100, 20000);
+        for resampler_type in [ResamplerType::Linear, ResamplerType::Sinc] {
+            let whole = Resampler::new(resampler_type, 44100, 30000).process(&input);
+            let mut resampler = Resampler::new(resampler_type, 44100, 30000);
+            let chunked: Vec<f32> = input
+                .chunks(333)
+                .flat_map(|chunk| resampler.process(chunk))
+                .collect();
+            assert_eq!(whole, chunked);
+        }
+    }
+
+    #[test]
+    fn output_rate_does_not_drift() {
+        for resampler_type in [ResamplerType::Linear, ResamplerType::Sinc] {
+            let mut resampler = Resampler::new(resampler_type, 44100, 30000);
+            let produced: usize = (0..1000)
+                .map(|_| resampler.process(&[0.0; 441]).len())
+                .sum();
+            // 10 s of input; only the kernel's look-ahead may still be pending.
+            assert!((300_000 - 30..=300_000).contains(&produced), "{}", produced);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use super::{should_stop, Pacer, SourceThread};
+use crate::audio::{AnalysisSink, AudioAnalysisData, DspSettings};
+use std::fs::File;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder, DecoderOptions};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use symphonia::core::units::Time;
+
+pub const FILE_DEVICE_PREFIX: &str = "File (";
+const CHUNK_DURATION: Duration = Duration::from_millis(10);
+
+/// Device name under which a file shows up, e.g. `File (/music/set.flac)`.
+pub fn file_device_name(path: &str) -> String {
+    format!("{}{})", FILE_DEVICE_PREFIX, path)
+}
+
+pub fn parse_file_device_name(name: &str) -> Option<&str> {
+    name.strip_prefix(FILE_DEVICE_PREFIX)
+        .and_then(|n| n.strip_suffix(')'))
+}
+
+struct FileControl {
+    looping: AtomicBool,
+    seek_to: Mutex<Option<f64>>,
+}
+
+/// Real-time playback of a WAV/FLAC/MP3 file through the DSP pipeline.
+///
+/// Nothing is sent to the speakers; the decoded samples are only analysed.
+pub struct FilePlayback {
+    control: Arc<FileControl>,
+    _thread: SourceThread,
+}
+
+struct OpenFile {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    sample_rate: u32,
+    channels: usize,
+}
+
+fn open_file(path: &Path) -> Result<OpenFile, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("Unsupported audio file {}: {}", path.display(), e))?;
+    let format = probed.format;
+    let track = format
+        .default_track()
+        .ok_or_else(|| format!("No audio track in {}", path.display()))?;
+    let track_id = track.id;
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| format!("Unknown sample rate in {}", path.display()))?;
+    let channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(1);
+    let decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| e.to_string())?;
+    Ok(OpenFile {
+        format,
+        decoder,
+        track_id,
+        sample_rate,
+        channels,
+    })
+}
+
+impl FilePlayback {
+    pub fn start(
+        path: &str,
+        looping: bool,
+        audio_data: Arc<Mutex<AudioAnalysisData>>,
+        dsp_settings: Arc<Mutex<DspSettings>>,
+    ) -> Result<Self, String> {
+        let file = open_file(Path::new(path))?;
+        println!(
+            "[AUDIO] Playing file {} ({} Hz, {} channels, loop: {})",
+            path, file.sample_rate, file.channels, looping
+        );
+        let control = Arc::new(FileControl {
+            looping: AtomicBool::new(looping),
+            seek_to: Mutex::new(None),
+        });
+        let thread_control = control.clone();
+        let thread = SourceThread::spawn("audio-file", move |stop| {
+            let sink = AnalysisSink::new(audio_data, dsp_settings);
+            run_playback(file, thread_control, sink, &stop);
+        })?;
+        Ok(Self {
+            control,
+            _thread: thread,
+        })
+    }
+
+    pub fn seek(&self, position_secs: f64) {
+        *self.control.seek_to.lock().unwrap() = Some(position_secs.max(0.0));
+    }
+
+    pub fn set_looping(&self, looping: bool) {
+        self.control.looping.store(looping, Ordering::SeqCst);
+    }
+}
+
+/// Returns whether the seek succeeded; failures are logged.
+fn seek(file: &mut OpenFile, position_secs: f64) -> bool {
+    let target = SeekTo::Time {
+        time: Time::from(position_secs),
+        track_id: Some(file.track_id),
+    };
+    let result = file.format.seek(SeekMode::Accurate, target);
+    file.decoder.reset();
+    match result {
+        Ok(_) => true,
+        Err(e) => {
+            eprintln!("[AUDIO] Failed to seek audio file: {}", e);
+            false
+        }
+    }
+}
+
+fn run_playback(
+    mut file: OpenFile,
+    control: Arc<FileControl>,
+    mut sink: AnalysisSink,
+    stop: &AtomicBool,
+) {
+    let mut pacer = Pacer::new(file.sample_rate);
+    let chunk_frames = pacer.chunk_frames(CHUNK_DURATION);
+    let mut finished = false;
+    // Set when looping back to the start, cleared by the next packet. Hitting the
+    // end again while it is still set means the file has nothing left to play.
+    let mut looped = false;
+
+    while !should_stop(stop) {
+        if let Some(position) = control.seek_to.lock().unwrap().take() {
+            seek(&mut file, position);
+            pacer.reset();
+            finished = false;
+            looped = false;
+        }
+
+        if finished {
+            // Keep the analysis decaying towards silence until a seek or source change.
+            let silence = vec![0.0; chunk_frames * file.channels];
+            sink.push(&silence, file.channels, file.sample_rate);
+            pacer.wait(chunk_frames);
+            continue;
+        }
+
+        let packet = match file.format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                if !control.looping.load(Ordering::SeqCst) {
+                    println!("[AUDIO] Audio file finished.");
+                    finished = true;
+                } else if looped || !seek(&mut file, 0.0) {
+                    // Stop instead of spinning between the end and a start we can't reach.
+                    eprintln!("[AUDIO] Can't loop audio file, stopping playback.");
+                    finished = true;
+                } else {
+                    looped = true;
+                }
+                continue;
+            }
+            Err(e) => {
+                eprintln!("[AUDIO] Error reading audio file: {}", e);
+                finished = true;
+                continue;
+            }
+        };
+        if packet.track_id() != file.track_id {
+            continue;
+        }
+        looped = false;
+
+        let decoded = match file.decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(SymphoniaError::DecodeError(e)) => {
+                eprintln!("[AUDIO] Skipping undecodable packet: {}", e);
+                continue;
+            }
+            Err(e) => {
+                eprintln!("[AUDIO] Error decoding audio file: {}", e);
+                finished = true;
+                continue;
+            }
+        };
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        file.channels = channels;
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+
+        for chunk in buffer.samples().chunks(chunk_frames * channels) {
+            if should_stop(stop) {
+                return;
+            }
+            sink.push(chunk, channels, spec.rate);
+            pacer.wait(chunk.len() / channels);
+        }
+    }
+}
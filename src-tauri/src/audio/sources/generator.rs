@@ -0,0 +1,341 @@
+use super::{should_stop, Pacer, SourceThread};
+use crate::audio::{AnalysisSink, AudioAnalysisData, DspSettings};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::TAU;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const GENERATOR_DEVICE_PREFIX: &str = "Generator (";
+const SAMPLE_RATE: u32 = 48000;
+const CHUNK_DURATION: Duration = Duration::from_millis(10);
+
+const SWEEP_START_HZ: f64 = 20.0;
+const SWEEP_END_HZ: f64 = 20000.0;
+const SWEEP_SECONDS: f64 = 10.0;
+const MULTI_TONE_HZ: [f64; 5] = [60.0, 250.0, 1000.0, 4000.0, 12000.0];
+const DEFAULT_CLICK_BPM: f32 = 120.0;
+const CLICK_HZ: f64 = 80.0;
+const CLICK_DECAY_SECONDS: f64 = 0.04;
+const CLICK_TRANSIENT_SECONDS: f64 = 0.002;
+
+/// Built-in test signals that can be selected like any other input device.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestSignal {
+    /// Logarithmic sweep from 20 Hz to 20 kHz, repeating every ten seconds.
+    SineSweep,
+    WhiteNoise,
+    PinkNoise,
+    /// A kick-like click on every beat.
+    ClickTrack {
+        bpm: f32,
+    },
+    /// Steady tones spread over the lows, mids and highs.
+    MultiTone,
+}
+
+impl TestSignal {
+    /// The signals listed as devices; any BPM can be requested for the click track by name.
+    pub fn presets() -> Vec<TestSignal> {
+        vec![
+            TestSignal::SineSweep,
+            TestSignal::WhiteNoise,
+            TestSignal::PinkNoise,
+            TestSignal::ClickTrack {
+                bpm: DEFAULT_CLICK_BPM,
+            },
+            TestSignal::MultiTone,
+        ]
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            TestSignal::SineSweep => "Sine Sweep".to_string(),
+            TestSignal::WhiteNoise => "White Noise".to_string(),
+            TestSignal::PinkNoise => "Pink Noise".to_string(),
+            TestSignal::ClickTrack { bpm } => format!("Click Track {} BPM", bpm),
+            TestSignal::MultiTone => "Multi-Tone".to_string(),
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "Sine Sweep" => Some(TestSignal::SineSweep),
+            "White Noise" => Some(TestSignal::WhiteNoise),
+            "Pink Noise" => Some(TestSignal::PinkNoise),
+            "Multi-Tone" => Some(TestSignal::MultiTone),
+            _ => {
+                let bpm: f32 = label
+                    .strip_prefix("Click Track ")?
+                    .strip_suffix(" BPM")?
+                    .trim()
+                    .parse()
+                    .ok()?;
+                (bpm > 0.0).then_some(TestSignal::ClickTrack { bpm })
+            }
+        }
+    }
+}
+
+/// Device name under which a signal shows up, e.g. `Generator (Pink Noise)`.
+pub fn generator_device_name(signal: &TestSignal) -> String {
+    format!("{}{})", GENERATOR_DEVICE_PREFIX, signal.label())
+}
+
+pub fn parse_generator_device_name(name: &str) -> Option<TestSignal> {
+    name.strip_prefix(GENERATOR_DEVICE_PREFIX)
+        .and_then(|n| n.strip_suffix(')'))
+        .and_then(TestSignal::from_label)
+}
+
+/// Sample-by-sample synthesis of a `TestSignal`.
+struct Synth {
+    signal: TestSignal,
+    sample_rate: f64,
+    position: u64,
+    phases: [f64; MULTI_TONE_HZ.len()],
+    pink: [f32; 7],
+    rng: StdRng,
+}
+
+impl Synth {
+    fn new(signal: TestSignal, sample_rate: u32) -> Self {
+        Self {
+            signal,
+            sample_rate: sample_rate as f64,
+            position: 0,
+            phases: [0.0; MULTI_TONE_HZ.len()],
+            pink: [0.0; 7],
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    fn white(&mut self) -> f32 {
+        self.rng.gen_range(-1.0..=1.0)
+    }
+
+    /// Paul Kellet's refined pink noise filter over white noise.
+    fn pink(&mut self) -> f32 {
+        let white = self.white();
+        let b = &mut self.pink;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.969 * b[2] + white * 0.153852;
+        b[3] = 0.8665 * b[3] + white * 0.3104856;
+        b[4] = 0.55 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let pink = b.iter().sum::<f32>() + white * 0.5362;
+        b[6] = white * 0.115926;
+        pink * 0.11
+    }
+
+    fn next_sample(&mut self) -> f32 {
+        let t = self.position as f64 / self.sample_rate;
+        self.position += 1;
+        match self.signal {
+            TestSignal::SineSweep => {
+                let progress = (t % SWEEP_SECONDS) / SWEEP_SECONDS;
+                let hz = SWEEP_START_HZ * (SWEEP_END_HZ / SWEEP_START_HZ).powf(progress);
+                self.phases[0] = (self.phases[0] + TAU * hz / self.sample_rate) % TAU;
+                (0.5 * self.phases[0].sin()) as f32
+            }
+            TestSignal::WhiteNoise => 0.5 * self.white(),
+            TestSignal::PinkNoise => self.pink(),
+            TestSignal::ClickTrack { bpm } => {
+                let since_beat = t % (60.0 / bpm as f64);
+                let body =
+                    (TAU * CLICK_HZ * since_beat).sin() * (-since_beat / CLICK_DECAY_SECONDS).exp();
+                let transient = if since_beat < CLICK_TRANSIENT_SECONDS {
+                    0.3 * self.white() as f64
+                } else {
+                    0.0
+                };
+                (0.8 * body + transient) as f32
+            }
+            TestSignal::MultiTone => {
+                let mut sum = 0.0;
+                for (phase, hz) in self.phases.iter_mut().zip(MULTI_TONE_HZ) {
+                    *phase = (*phase + TAU * hz / self.sample_rate) % TAU;
+                    sum += phase.sin();
+                }
+                (0.15 * sum) as f32
+            }
+        }
+    }
+}
+
+/// Real-time test-signal source feeding the DSP pipeline.
+pub struct SignalGenerator {
+    _thread: SourceThread,
+}
+
+impl SignalGenerator {
+    pub fn start(
+        signal: TestSignal,
+        audio_data: Arc<Mutex<AudioAnalysisData>>,
+        dsp_settings: Arc<Mutex<DspSettings>>,
+    ) -> Result<Self, String> {
+        println!("[AUDIO] Starting test signal generator: {}", signal.label());
+        let thread = SourceThread::spawn("audio-generator", move |stop| {
+            let mut sink = AnalysisSink::new(audio_data, dsp_settings);
+            let mut synth = Synth::new(signal, SAMPLE_RATE);
+            let mut pacer = Pacer::new(SAMPLE_RATE);
+            let mut chunk = vec![0.0; pacer.chunk_frames(CHUNK_DURATION)];
+            while !should_stop(&stop) {
+                for sample in chunk.iter_mut() {
+                    *sample = synth.next_sample();
+                }
+                sink.push(&chunk, 1, SAMPLE_RATE);
+                pacer.wait(chunk.len());
+            }
+        })?;
+        Ok(Self { _thread: thread })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioProcessor;
+    use crate::utils::dsp::center_frequencies;
+
+    const FFT_SIZE: usize = 2048;
+
+    fn test_settings() -> DspSettings {
+        DspSettings {
+            fft_size: FFT_SIZE as u32,
+            sample_rate: None,
+            smoothing_factor: 0.0,
+            agc_attack: 1.0,
+            ..Default::default()
+        }
+    }
+
+    /// Runs `seconds` of `signal` through an `AudioProcessor`, one frame per call.
+    fn analyse(signal: TestSignal, seconds: f64) -> Vec<AudioAnalysisData> {
+        let settings = test_settings();
+        let mut processor = AudioProcessor::new(&settings, SAMPLE_RATE);
+        let mut synth = Synth::new(signal, SAMPLE_RATE);
+        let frames = (seconds * SAMPLE_RATE as f64) as usize / FFT_SIZE;
+        (0..frames)
+            .filter_map(|_| {
+                let chunk: Vec<f32> = (0..FFT_SIZE).map(|_| synth.next_sample()).collect();
+                processor.process(&chunk, 1, SAMPLE_RATE, &settings)
+            })
+            .collect()
+    }
+
+    fn band_of(hz: f32) -> usize {
+        let settings = test_settings();
+        let centers = center_frequencies(
+            settings.num_bands as usize,
+            settings.min_freq,
+            settings.max_freq,
+            &settings.filterbank_type,
+        );
+        centers
+            .iter()
+            .enumerate()
+            .min_by(|a, b| (a.1 - hz).abs().total_cmp(&(b.1 - hz).abs()))
+            .map(|(i, _)| i)
+            .unwrap()
+    }
+
+    fn loudest_band(analysis: &AudioAnalysisData) -> usize {
+        analysis
+            .melbanks
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, _)| i)
+            .unwrap()
+    }
+
+    #[test]
+    fn labels_round_trip() {
+        for signal in TestSignal::presets()
+            .into_iter()
+            .chain([TestSignal::ClickTrack { bpm: 97.5 }])
+        {
+            assert_eq!(TestSignal::from_label(&signal.label()), Some(signal));
+            assert_eq!(
+                parse_generator_device_name(&generator_device_name(&signal)),
+                Some(signal)
+            );
+        }
+        assert_eq!(
+            TestSignal::from_label("Click Track 128 BPM"),
+            Some(TestSignal::ClickTrack { bpm: 128.0 })
+        );
+        assert_eq!(TestSignal::from_label("Click Track 0 BPM"), None);
+        assert_eq!(TestSignal::from_label("Click Track fast BPM"), None);
+        assert_eq!(TestSignal::from_label("Brown Noise"), None);
+    }
+
+    #[test]
+    fn multi_tone_fills_the_bands_of_its_tones() {
+        let analysis = analyse(TestSignal::MultiTone, 1.0);
+        let melbanks = &analysis.last().unwrap().melbanks;
+        let mean = melbanks.iter().sum::<f32>() / melbanks.len() as f32;
+        for hz in MULTI_TONE_HZ {
+            let band = band_of(hz as f32);
+            assert!(
+                melbanks[band] > mean,
+                "{} Hz band {} has {} (mean {})",
+                hz,
+                band,
+                melbanks[band],
+                mean
+            );
+        }
+        // Between two tones there is little energy.
+        assert!(melbanks[band_of(2000.0)] < melbanks[band_of(1000.0)] / 2.0);
+    }
+
+    #[test]
+    fn sweep_moves_up_through_the_bands() {
+        let analysis = analyse(TestSignal::SineSweep, SWEEP_SECONDS * 0.9);
+        let frames_per_second = SAMPLE_RATE as f64 / FFT_SIZE as f64;
+        for seconds in [3.0, 5.0, 7.0] {
+            let frame = (seconds * frames_per_second) as usize;
+            // The middle of the analysed window.
+            let t = (frame as f64 + 0.5) / frames_per_second;
+            let hz = SWEEP_START_HZ * (SWEEP_END_HZ / SWEEP_START_HZ).powf(t / SWEEP_SECONDS);
+            let loudest = loudest_band(&analysis[frame]);
+            let expected = band_of(hz as f32);
+            assert!(
+                loudest.abs_diff(expected) <= 1,
+                "at {:.1} s ({:.0} Hz): band {} vs {}",
+                t,
+                hz,
+                loudest,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn click_track_beats_follow_the_bpm() {
+        let bpm = 120.0;
+        let analysis = analyse(TestSignal::ClickTrack { bpm }, 6.0);
+        let frame_secs = FFT_SIZE as f64 / SAMPLE_RATE as f64;
+        let beats: Vec<f64> = analysis
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| a.beat)
+            .map(|(i, _)| i as f64 * frame_secs)
+            .collect();
+        assert!(beats.len() >= 10, "only {} beats: {:?}", beats.len(), beats);
+        let interval = 60.0 / bpm as f64;
+        for pair in beats.windows(2) {
+            let spacing = pair[1] - pair[0];
+            assert!(
+                (spacing - interval).abs() <= frame_secs * 1.5,
+                "beats {:.3} s apart, expected {:.3} s: {:?}",
+                spacing,
+                interval,
+                beats
+            );
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+pub mod file;
+pub mod generator;
+pub mod network;
+pub mod replay;
+
+/// A non-cpal audio source running on its own thread.
+///
+/// The thread is asked to stop and joined when this handle is dropped.
+pub struct SourceThread {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl SourceThread {
+    pub fn spawn<F>(name: &str, body: F) -> Result<Self, String>
+    where
+        F: FnOnce(Arc<AtomicBool>) + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let handle = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || body(thread_stop))
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            stop,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for SourceThread {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+pub fn should_stop(stop: &AtomicBool) -> bool {
+    stop.load(Ordering::SeqCst)
+}
+
+/// Keeps a generated or decoded stream running at real-time speed.
+pub struct Pacer {
+    sample_rate: u32,
+    start: Instant,
+    frames: u64,
+}
+
+impl Pacer {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1),
+            start: Instant::now(),
+            frames: 0,
+        }
+    }
+
+    /// Starts counting from now again, e.g. after a seek.
+    pub fn reset(&mut self) {
+        self.start = Instant::now();
+        self.frames = 0;
+    }
+
+    /// Number of frames in a chunk of the given duration.
+    pub fn chunk_frames(&self, duration: Duration) -> usize {
+        ((self.sample_rate as f64 * duration.as_secs_f64()) as usize).max(1)
+    }
+
+    /// Records `frames` as delivered and sleeps until they are due in real time.
+    pub fn wait(&mut self, frames: usize) {
+        self.frames += frames as u64;
+        let due =
+            self.start + Duration::from_secs_f64(self.frames as f64 / self.sample_rate as f64);
+        if let Some(remaining) = due.checked_duration_since(Instant::now()) {
+            thread::sleep(remaining);
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use super::{should_stop, Pacer, SourceThread};
+use crate::audio::{AnalysisSink, AudioAnalysisData, DspSettings, NetworkInputSettings, PcmFormat};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::ErrorKind;
+use std::net::UdpSocket;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const NETWORK_DEVICE_NAME: &str = "Network (UDP/RTP)";
+const CHUNK_DURATION: Duration = Duration::from_millis(10);
+// Drop audio once this many jitter buffers worth has piled up, so latency can't grow unbounded.
+const MAX_BUFFER_FACTOR: usize = 4;
+// Out-of-order RTP packets waiting for a gap to fill before the gap is declared lost.
+const MAX_PENDING_PACKETS: usize = 8;
+const MAX_PACKET_SIZE: usize = 65536;
+
+/// Decodes interleaved PCM bytes into f32 samples, ignoring a trailing partial sample.
+fn decode_pcm(payload: &[u8], format: PcmFormat) -> Vec<f32> {
+    let bytes = format.bytes_per_sample();
+    payload
+        .chunks_exact(bytes)
+        .map(|b| match format {
+            PcmFormat::S16Le => i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+            PcmFormat::S16Be => i16::from_be_bytes([b[0], b[1]]) as f32 / 32768.0,
+            PcmFormat::F32Le => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            PcmFormat::F32Be => f32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+        })
+        .collect()
+}
+
+/// Splits an RTP packet into its sequence number and payload.
+fn parse_rtp(packet: &[u8]) -> Option<(u16, &[u8])> {
+    if packet.len() < 12 || packet[0] >> 6 != 2 {
+        return None;
+    }
+    let has_padding = packet[0] & 0x20 != 0;
+    let has_extension = packet[0] & 0x10 != 0;
+    let csrc_count = (packet[0] & 0x0f) as usize;
+    let sequence = u16::from_be_bytes([packet[2], packet[3]]);
+
+    let mut start = 12 + 4 * csrc_count;
+    if has_extension {
+        let words = packet.get(start + 2..start + 4)?;
+        start += 4 + 4 * u16::from_be_bytes([words[0], words[1]]) as usize;
+    }
+    let mut end = packet.len();
+    if has_padding {
+        end = end.checked_sub(*packet.last()? as usize)?;
+    }
+    packet.get(start..end).map(|payload| (sequence, payload))
+}
+
+/// Reorders RTP packets, conceals lost ones with silence and smooths out arrival jitter.
+struct JitterBuffer {
+    channels: usize,
+    target_samples: usize,
+    max_samples: usize,
+    ready: VecDeque<f32>,
+    pending: BTreeMap<u64, Vec<f32>>,
+    next_sequence: Option<u64>,
+    last_sequence: Option<u64>,
+    buffering: bool,
+}
+
+impl JitterBuffer {
+    fn new(settings: &NetworkInputSettings) -> Self {
+        let channels = settings.channels.max(1) as usize;
+        let target_frames =
+            (settings.sample_rate as u64 * settings.jitter_buffer_ms as u64 / 1000) as usize;
+        let target_samples = target_frames.max(1) * channels;
+        Self {
+            channels,
+            target_samples,
+            max_samples: target_samples * MAX_BUFFER_FACTOR,
+            ready: VecDeque::new(),
+            pending: BTreeMap::new(),
+            next_sequence: None,
+            last_sequence: None,
+            buffering: true,
+        }
+    }
+
+    /// Unwraps the 16-bit RTP sequence number relative to the last one seen.
+    fn extend_sequence(&mut self, sequence: u16) -> u64 {
+        let extended = match self.last_sequence {
+            None => sequence as u64 + (1 << 16),
+            Some(last) => {
+                let delta = sequence.wrapping_sub(last as u16) as i16 as i64;
+                (last as i64 + delta).max(0) as u64
+            }
+        };
+        self.last_sequence = Some(self.last_sequence.map_or(extended, |l| l.max(extended)));
+        extended
+    }
+
+    fn push_rtp(&mut self, sequence: u16, samples: Vec<f32>) {
+        let sequence = self.extend_sequence(sequence);
+        let next = *self.next_sequence.get_or_insert(sequence);
+        if sequence < next {
+            // Too late, the gap was already filled with silence.
+            return;
+        }
+        self.pending.insert(sequence, samples);
+        self.release();
+    }
+
+    fn push_raw(&mut self, samples: Vec<f32>) {
+        self.ready.extend(samples);
+        self.trim();
+    }
+
+    fn release(&mut self) {
+        while let Some(next) = self.next_sequence {
+            if let Some(samples) = self.pending.remove(&next) {
+                self.ready.extend(samples);
+                self.next_sequence = Some(next + 1);
+            } else if self.pending.len() > MAX_PENDING_PACKETS {
+                let (&first, samples) = self.pending.iter().next().unwrap();
+                let lost = (first - next) as usize * samples.len();
+                self.ready.extend(std::iter::repeat_n(0.0, lost));
+                self.next_sequence = Some(first);
+            } else {
+                break;
+            }
+        }
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        if self.ready.len() > self.max_samples {
+            let excess = self.ready.len() - self.target_samples;
+            let excess = excess - excess % self.channels;
+            self.ready.drain(..excess);
+        }
+    }
+
+    /// Takes `frames` frames for playout, padding with silence on underrun.
+    fn pull(&mut self, frames: usize) -> Vec<f32> {
+        let wanted = frames * self.channels;
+        if self.buffering {
+            if self.ready.len() < self.target_samples {
+                return vec![0.0; wanted];
+            }
+            self.buffering = false;
+        }
+        let available = wanted.min(self.ready.len());
+        let mut out: Vec<f32> = self.ready.drain(..available).collect();
+        if out.len() < wanted {
+            self.buffering = true;
+            out.resize(wanted, 0.0);
+        }
+        out
+    }
+}
+
+/// Receives raw or RTP-wrapped PCM over UDP and feeds it through the DSP pipeline.
+pub struct NetworkReceiver {
+    _thread: SourceThread,
+}
+
+impl NetworkReceiver {
+    pub fn start(
+        audio_data: Arc<Mutex<AudioAnalysisData>>,
+        dsp_settings: Arc<Mutex<DspSettings>>,
+    ) -> Result<Self, String> {
+        let settings = dsp_settings.lock().unwrap().network_input.clone();
+        if settings.channels == 0 || settings.sample_rate == 0 {
+            return Err("Network input needs at least one channel and a sample rate".to_string());
+        }
+        let socket = UdpSocket::bind(("0.0.0.0", settings.port))
+            .map_err(|e| format!("Could not listen on UDP port {}: {}", settings.port, e))?;
+        socket.set_nonblocking(true).map_err(|e| e.to_string())?;
+        println!(
+            "[AUDIO] Listening for {} PCM on UDP port {} ({:?}, {} channels, {} Hz)",
+            if settings.rtp { "RTP" } else { "raw" },
+            settings.port,
+            settings.format,
+            settings.channels,
+            settings.sample_rate
+        );
+
+        let thread = SourceThread::spawn("audio-network", move |stop| {
+            let mut sink = AnalysisSink::new(audio_data, dsp_settings);
+            let mut jitter_buffer = JitterBuffer::new(&settings);
+            let mut pacer = Pacer::new(settings.sample_rate);
+            let chunk_frames = pacer.chunk_frames(CHUNK_DURATION);
+            let channels = settings.channels as usize;
+            let mut packet = vec![0u8; MAX_PACKET_SIZE];
+
+            while !should_stop(&stop) {
+                loop {
+                    match socket.recv_from(&mut packet) {
+                        Ok((len, _)) => {
+                            let packet = &packet[..len];
+                            if settings.rtp {
+                                if let Some((sequence, payload)) = parse_rtp(packet) {
+                                    jitter_buffer
+                                        .push_rtp(sequence, decode_pcm(payload, settings.format));
+                                }
+                            } else {
+                                jitter_buffer.push_raw(decode_pcm(packet, settings.format));
+                            }
+                        }
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                        Err(e) => {
+                            eprintln!("[AUDIO] Network input receive error: {}", e);
+                            break;
+                        }
+                    }
+                }
+
+                let chunk = jitter_buffer.pull(chunk_frames);
+                sink.push(&chunk, channels, settings.sample_rate);
+                pacer.wait(chunk_frames);
+            }
+        })?;
+        Ok(Self { _thread: thread })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 10 ms at 1 kHz mono: a 10 sample jitter buffer that trims above 40 samples.
+    fn test_buffer(channels: u16) -> JitterBuffer {
+        JitterBuffer::new(&NetworkInputSettings {
+            channels,
+            sample_rate: 1000,
+            jitter_buffer_ms: 10,
+            ..Default::default()
+        })
+    }
+
+    /// Two samples tagged with the packet they came from, so silence (0.0) stands out.
+    fn packet(tag: u16) -> Vec<f32> {
+        vec![tag as f32 + 1.0; 2]
+    }
+
+    fn ready(buffer: &JitterBuffer) -> Vec<f32> {
+        buffer.ready.iter().copied().collect()
+    }
+
+    #[test]
+    fn reorders_out_of_order_packets() {
+        let mut buffer = test_buffer(1);
+        buffer.push_rtp(10, packet(10));
+        buffer.push_rtp(12, packet(12));
+        buffer.push_rtp(13, packet(13));
+        assert_eq!(ready(&buffer), [11.0, 11.0]);
+
+        buffer.push_rtp(11, packet(11));
+        assert_eq!(
+            ready(&buffer),
+            [11.0, 11.0, 12.0, 12.0, 13.0, 13.0, 14.0, 14.0]
+        );
+    }
+
+    #[test]
+    fn conceals_a_lost_packet_with_silence() {
+        let mut buffer = test_buffer(1);
+        buffer.push_rtp(0, packet(0));
+        for sequence in 2..2 + MAX_PENDING_PACKETS as u16 {
+            buffer.push_rtp(sequence, packet(sequence));
+        }
+        // Still waiting for packet 1.
+        assert_eq!(buffer.ready.len(), 2);
+
+        buffer.push_rtp(10, packet(10));
+        let samples = ready(&buffer);
+        assert_eq!(samples[..4], [1.0, 1.0, 0.0, 0.0]);
+        assert_eq!(samples[4..6], [3.0, 3.0]);
+        assert_eq!(samples.len(), 2 * 11);
+        assert!(buffer.pending.is_empty());
+    }
+
+    #[test]
+    fn drops_packets_arriving_after_their_gap_was_concealed() {
+        let mut buffer = test_buffer(1);
+        buffer.push_rtp(0, packet(0));
+        for sequence in 2..=2 + MAX_PENDING_PACKETS as u16 {
+            buffer.push_rtp(sequence, packet(sequence));
+        }
+        let before = ready(&buffer);
+
+        buffer.push_rtp(1, packet(1));
+        assert_eq!(ready(&buffer), before);
+        assert!(buffer.pending.is_empty());
+    }
+
+    #[test]
+    fn trims_back_to_the_target_above_max_samples() {
+        let mut buffer = test_buffer(2);
+        assert_eq!((buffer.target_samples, buffer.max_samples), (20, 80));
+
+        buffer.push_raw((0..80).map(|i| i as f32).collect());
+        assert_eq!(buffer.ready.len(), 80);
+
+        buffer.push_raw((80..90).map(|i| i as f32).collect());
+        assert_eq!(
+            ready(&buffer),
+            (70..90).map(|i| i as f32).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn trims_whole_frames_only() {
+        let mut buffer = test_buffer(2);
+        buffer.push_raw((0..81).map(|i| i as f32).collect());
+        // 61 excess samples round down to 30 frames, keeping channels aligned.
+        assert_eq!(buffer.ready.len(), 21);
+        assert_eq!(buffer.ready[0], 60.0);
+    }
+
+    #[test]
+    fn rebuffers_after_an_underrun() {
+        let mut buffer = test_buffer(1);
+        buffer.push_raw(vec![1.0; 9]);
+        assert_eq!(buffer.pull(4), [0.0; 4]);
+
+        buffer.push_raw(vec![1.0; 1]);
+        assert_eq!(buffer.pull(4), [1.0; 4]);
+
+        // Only 6 samples left: the rest is padded and playout waits for a full buffer again.
+        assert_eq!(buffer.pull(8), [1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0]);
+        buffer.push_raw(vec![1.0; 4]);
+        assert_eq!(buffer.pull(2), [0.0; 2]);
+        buffer.push_raw(vec![1.0; 6]);
+        assert_eq!(buffer.pull(2), [1.0; 2]);
+    }
+
+    #[test]
+    fn follows_the_sequence_number_across_wraparound() {
+        let mut buffer = test_buffer(1);
+        buffer.push_rtp(65534, packet(1));
+        buffer.push_rtp(0, packet(3));
+        buffer.push_rtp(65535, packet(2));
+        buffer.push_rtp(1, packet(4));
+        assert_eq!(ready(&buffer), [2.0, 2.0, 3.0, 3.0, 4.0, 4.0, 5.0, 5.0]);
+
+        // A packet from before the wrap is still recognised as late.
+        buffer.push_rtp(65533, packet(0));
+        assert_eq!(buffer.ready.len(), 8);
+        assert!(buffer.pending.is_empty());
+    }
+
+    #[test]
+    fn parses_plain_rtp_headers() {
+        let mut packet = vec![0x80, 96, 0x12, 0x34, 0, 0, 0, 0, 0, 0, 0, 0];
+        packet.extend([1, 2, 3, 4]);
+        assert_eq!(parse_rtp(&packet), Some((0x1234, &[1, 2, 3, 4][..])));
+    }
+
+    #[test]
+    fn skips_csrcs_extension_and_padding() {
+        // Padding, extension and two CSRCs.
+        let mut packet = vec![0xb2, 96, 0xff, 0xfe, 0, 0, 0, 0, 0, 0, 0, 0];
+        packet.extend([0xaa; 8]);
+        // Extension header announcing one 32-bit word.
+        packet.extend([0xbe, 0xde, 0, 1, 0xcc, 0xcc, 0xcc, 0xcc]);
+        packet.extend([1, 2, 3, 4]);
+        packet.extend([0, 0, 3]);
+        assert_eq!(parse_rtp(&packet), Some((0xfffe, &[1, 2, 3, 4][..])));
+    }
+
+    #[test]
+    fn rejects_malformed_rtp_packets() {
+        assert_eq!(parse_rtp(&[0x80; 11]), None);
+        // Wrong version.
+        assert_eq!(parse_rtp(&[0x40; 16]), None);
+        // Extension header cut off.
+        assert_eq!(
+            parse_rtp(&[0x90, 96, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            None
+        );
+        // Padding longer than the packet.
+        let mut packet = vec![0xa0, 96, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0];
+        packet.push(200);
+        assert_eq!(parse_rtp(&packet), None);
+    }
+
+    #[test]
+    fn decodes_every_pcm_format() {
+        assert_eq!(
+            decode_pcm(&[0x00, 0x40, 0x00, 0xc0, 0x7f], PcmFormat::S16Le),
+            [0.5, -0.5]
+        );
+        assert_eq!(
+            decode_pcm(&[0x40, 0x00, 0xc0, 0x00], PcmFormat::S16Be),
+            [0.5, -0.5]
+        );
+        assert_eq!(decode_pcm(&0.25f32.to_le_bytes(), PcmFormat::F32Le), [0.25]);
+        assert_eq!(decode_pcm(&0.25f32.to_be_bytes(), PcmFormat::F32Be), [0.25]);
+    }
+}
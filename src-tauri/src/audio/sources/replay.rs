@@ -0,0 +1,155 @@
+use super::{should_stop, SourceThread};
+use crate::audio::recording::{read_analysis_file, RecordedFrame};
+use crate::audio::AudioAnalysisData;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const REPLAY_DEVICE_PREFIX: &str = "Replay (";
+// Longest sleep between stop checks while waiting for the next frame.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Device name under which a replayed recording shows up, e.g. `Replay (/rec/set.analysis.jsonl)`.
+pub fn replay_device_name(path: &str) -> String {
+    format!("{}{})", REPLAY_DEVICE_PREFIX, path)
+}
+
+pub fn parse_replay_device_name(name: &str) -> Option<&str> {
+    name.strip_prefix(REPLAY_DEVICE_PREFIX)
+        .and_then(|n| n.strip_suffix(')'))
+}
+
+/// Publishes the frames of an analysis recording at the times they were recorded.
+///
+/// The DSP pipeline is bypassed, so effects see exactly what was captured
+/// regardless of the current DSP settings.
+pub struct AnalysisReplay {
+    _thread: SourceThread,
+}
+
+impl AnalysisReplay {
+    pub fn start(
+        path: &str,
+        looping: bool,
+        audio_data: Arc<Mutex<AudioAnalysisData>>,
+    ) -> Result<Self, String> {
+        let frames = read_analysis_file(Path::new(path))?;
+        if frames.is_empty() {
+            return Err(format!("No analysis frames in {}", path));
+        }
+        println!(
+            "[AUDIO] Replaying {} analysis frames from {} (loop: {})",
+            frames.len(),
+            path,
+            looping
+        );
+        let thread = SourceThread::spawn("audio-replay", move |stop| {
+            run_replay(&frames, looping, &audio_data, &stop);
+        })?;
+        Ok(Self { _thread: thread })
+    }
+}
+
+fn publish(audio_data: &Mutex<AudioAnalysisData>, mut analysis: AudioAnalysisData) {
+    if let Ok(mut data) = audio_data.lock() {
+        analysis.sequence = data.sequence.wrapping_add(1);
+        *data = analysis;
+    }
+}
+
+fn run_replay(
+    frames: &[RecordedFrame],
+    looping: bool,
+    audio_data: &Mutex<AudioAnalysisData>,
+    stop: &AtomicBool,
+) {
+    let last = &frames[frames.len() - 1];
+    loop {
+        let start = Instant::now();
+        for frame in frames {
+            let due = start + Duration::from_secs_f64(frame.time.max(0.0));
+            while let Some(remaining) = due.checked_duration_since(Instant::now()) {
+                if should_stop(stop) {
+                    return;
+                }
+                thread::sleep(remaining.min(POLL_INTERVAL));
+            }
+            if should_stop(stop) {
+                return;
+            }
+            publish(audio_data, frame.analysis.clone());
+        }
+        // A recording without any duration would otherwise loop without sleeping.
+        if !looping || last.time <= 0.0 {
+            break;
+        }
+    }
+
+    println!("[AUDIO] Analysis replay finished.");
+    publish(
+        audio_data,
+        AudioAnalysisData {
+            band_ranges: last.analysis.band_ranges,
+            ..AudioAnalysisData::new(last.analysis.melbanks.len())
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::recording;
+
+    #[test]
+    fn replays_what_was_recorded() {
+        let directory =
+            std::env::temp_dir().join(format!("ledfx-replay-test-{}", std::process::id()));
+        let recorded = Arc::new(Mutex::new(AudioAnalysisData::default()));
+        recording::start(&directory, recorded.clone()).unwrap();
+        recording::record_input(&recorded, &[0.5; 960], 2, 48000);
+        for i in 0..3 {
+            let analysis = AudioAnalysisData {
+                melbanks: vec![i as f32 + 1.0; 4],
+                beat: i == 1,
+                ..Default::default()
+            };
+            recording::record_analysis(&recorded, &analysis);
+            thread::sleep(Duration::from_millis(50));
+        }
+        let info = recording::stop().unwrap();
+        assert_eq!(info.analysis_frames, 3);
+        assert_eq!(info.duration_secs, 0.01);
+        assert_eq!(
+            std::fs::metadata(&info.wav_path).unwrap().len(),
+            44 + 960 * 2
+        );
+
+        let frames = read_analysis_file(Path::new(&info.analysis_path)).unwrap();
+        let beats: Vec<bool> = frames.iter().map(|f| f.analysis.beat).collect();
+        assert_eq!(beats, vec![false, true, false]);
+        assert!(frames.windows(2).all(|w| w[1].time - w[0].time >= 0.05));
+
+        let replayed = Arc::new(Mutex::new(AudioAnalysisData::default()));
+        let _replay = AnalysisReplay::start(&info.analysis_path, false, replayed.clone()).unwrap();
+        let mut seen = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        // The last frame is replaced right away by the silent frame that ends the
+        // replay, so only the first two can be watched reliably.
+        loop {
+            assert!(Instant::now() < deadline, "replay stalled after {:?}", seen);
+            let data = replayed.lock().unwrap().clone();
+            if data.sequence == 4 {
+                assert!(data.melbanks.iter().all(|&v| v == 0.0));
+                break;
+            }
+            if data.sequence as usize == seen.len() + 1 {
+                seen.push((data.melbanks[0], data.beat));
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        let _ = std::fs::remove_dir_all(&directory);
+        assert_eq!(seen[..2], [(1.0, false), (2.0, true)]);
+    }
+}
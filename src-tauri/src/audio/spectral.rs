@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+// Fraction of the spectral energy below the rolloff frequency.
+const ROLLOFF_FRACTION: f32 = 0.85;
+// Pitch range folded into the chromagram (A1 to roughly D#8).
+const CHROMA_MIN_HZ: f32 = 55.0;
+const CHROMA_MAX_HZ: f32 = 5000.0;
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Timbre and pitch descriptors of one analysis frame.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, Default)]
+pub struct SpectralFeatures {
+    /// Center of mass of the spectrum in Hz ("brightness").
+    pub centroid: f32,
+    /// How much the shape of the spectrum changed since the last frame, 0..1.
+    pub flux: f32,
+    /// Frequency in Hz below which 85% of the spectral energy lies.
+    pub rolloff: f32,
+    /// Strongest frequency between the filterbank's min and max frequency, in Hz.
+    pub dominant_freq: f32,
+    /// Nearest note to `dominant_freq`, e.g. "A4".
+    pub dominant_note: Option<String>,
+    /// Energy per pitch class, C to B, scaled so the strongest is 1.
+    pub chroma: Vec<f32>,
+}
+
+fn hz_to_midi(hz: f32) -> f32 {
+    69.0 + 12.0 * (hz / 440.0).log2()
+}
+
+pub fn note_name(hz: f32) -> Option<String> {
+    if hz <= 0.0 {
+        return None;
+    }
+    let midi = hz_to_midi(hz).round() as i32;
+    if midi < 0 {
+        return None;
+    }
+    Some(format!(
+        "{}{}",
+        NOTE_NAMES[(midi % 12) as usize],
+        midi / 12 - 1
+    ))
+}
+
+/// Computes `SpectralFeatures` from the FFT magnitudes of consecutive frames.
+pub struct SpectralAnalyzer {
+    bin_hz: f32,
+    dominant_bins: (usize, usize),
+    /// Pitch class of every bin inside the chromagram range.
+    pitch_classes: Vec<Option<usize>>,
+    previous: Vec<f32>,
+}
+
+impl SpectralAnalyzer {
+    pub fn new(num_bins: usize, sample_rate: u32, min_freq: f32, max_freq: f32) -> Self {
+        let bin_hz = sample_rate as f32 / (2 * num_bins) as f32;
+        let to_bin = |hz: f32| ((hz / bin_hz).round() as usize).clamp(1, num_bins.max(2) - 1);
+        let pitch_classes = (0..num_bins)
+            .map(|k| {
+                let hz = k as f32 * bin_hz;
+                (CHROMA_MIN_HZ..=CHROMA_MAX_HZ)
+                    .contains(&hz)
+                    .then(|| hz_to_midi(hz).round().rem_euclid(12.0) as usize)
+            })
+            .collect();
+        Self {
+            bin_hz,
+            dominant_bins: (to_bin(min_freq), to_bin(max_freq)),
+            pitch_classes,
+            previous: Vec::new(),
+        }
+    }
+
+    /// Forgets the previous frame, so flux starts from zero again (e.g. after silence).
+    pub fn reset(&mut self) {
+        self.previous.clear();
+    }
+
+    pub fn process(&mut self, magnitudes: &[f32]) -> SpectralFeatures {
+        let total: f32 = magnitudes.iter().sum();
+        if total <= f32::EPSILON || magnitudes.len() < 3 {
+            self.reset();
+            return SpectralFeatures {
+                chroma: vec![0.0; 12],
+                ..Default::default()
+            };
+        }
+
+        let centroid = magnitudes
+            .iter()
+            .enumerate()
+            .map(|(k, &m)| k as f32 * self.bin_hz * m)
+            .sum::<f32>()
+            / total;
+
+        // Half-wave rectified difference of the sum-normalized spectra, so loudness changes
+        // alone don't count as flux.
+        let normalized: Vec<f32> = magnitudes.iter().map(|m| m / total).collect();
+        let flux = if self.previous.len() == normalized.len() {
+            normalized
+                .iter()
+                .zip(&self.previous)
+                .map(|(now, before)| (now - before).max(0.0))
+                .sum()
+        } else {
+            0.0
+        };
+        self.previous = normalized;
+
+        let total_energy: f32 = magnitudes.iter().map(|m| m * m).sum();
+        let mut cumulative = 0.0;
+        let rolloff_bin = magnitudes
+            .iter()
+            .position(|m| {
+                cumulative += m * m;
+                cumulative >= ROLLOFF_FRACTION * total_energy
+            })
+            .unwrap_or(magnitudes.len() - 1);
+
+        let (low, high) = self.dominant_bins;
+        let high = high.min(magnitudes.len() - 2);
+        let dominant_freq = (low..=high)
+            .max_by(|&a, &b| magnitudes[a].total_cmp(&magnitudes[b]))
+            .map(|k| {
+                // Parabolic interpolation between the neighbouring bins.
+                let (left, center, right) = (magnitudes[k - 1], magnitudes[k], magnitudes[k + 1]);
+                let denominator = left - 2.0 * center + right;
+                let offset = if denominator.abs() > f32::EPSILON {
+                    (0.5 * (left - right) / denominator).clamp(-0.5, 0.5)
+                } else {
+                    0.0
+                };
+                (k as f32 + offset) * self.bin_hz
+            })
+            .unwrap_or(0.0);
+
+        let mut chroma = vec![0.0f32; 12];
+        for (m, pitch_class) in magnitudes.iter().zip(&self.pitch_classes) {
+            if let Some(pitch_class) = pitch_class {
+                chroma[*pitch_class] += m * m;
+            }
+        }
+        let chroma_max = chroma.iter().fold(0.0f32, |max, &v| max.max(v));
+        if chroma_max > 0.0 {
+            chroma.iter_mut().for_each(|v| *v /= chroma_max);
+        }
+
+        SpectralFeatures {
+            centroid,
+            flux,
+            rolloff: rolloff_bin as f32 * self.bin_hz,
+            dominant_freq,
+            dominant_note: note_name(dominant_freq),
+            chroma,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1 Hz per bin keeps the expected frequencies readable.
+    const NUM_BINS: usize = 1024;
+    const SAMPLE_RATE: u32 = 2048;
+
+    fn analyzer() -> SpectralAnalyzer {
+        SpectralAnalyzer::new(NUM_BINS, SAMPLE_RATE, 20.0, 1000.0)
+    }
+
+    fn spectrum(peaks: &[(usize, f32)]) -> Vec<f32> {
+        let mut magnitudes = vec![0.0; NUM_BINS];
+        for &(bin, magnitude) in peaks {
+            magnitudes[bin] = magnitude;
+        }
+        magnitudes
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn centroid_is_the_magnitude_weighted_mean() {
+        let features = analyzer().process(&spectrum(&[(100, 1.0), (300, 1.0)]));
+        assert_close(features.centroid, 200.0);
+
+        let features = analyzer().process(&spectrum(&[(100, 3.0), (300, 1.0)]));
+        assert_close(features.centroid, 150.0);
+    }
+
+    #[test]
+    fn rolloff_is_where_85_percent_of_the_energy_is_reached() {
+        let features =
+            analyzer().process(&spectrum(&[(100, 1.0), (200, 1.0), (300, 1.0), (400, 1.0)]));
+        assert_close(features.rolloff, 400.0);
+
+        // 90% of the energy sits in the lowest peak.
+        let features = analyzer().process(&spectrum(&[(100, 3.0), (500, 1.0)]));
+        assert_close(features.rolloff, 100.0);
+    }
+
+    #[test]
+    fn dominant_frequency_is_interpolated_between_bins() {
+        let features = analyzer().process(&spectrum(&[(439, 0.5), (440, 1.0), (441, 0.5)]));
+        assert_close(features.dominant_freq, 440.0);
+
+        // Samples of a parabola peaking at 440.25 Hz.
+        let parabola = |hz: f32| 10.0 - (hz - 440.25) * (hz - 440.25);
+        let features = analyzer().process(&spectrum(&[
+            (439, parabola(439.0)),
+            (440, parabola(440.0)),
+            (441, parabola(441.0)),
+        ]));
+        assert_close(features.dominant_freq, 440.25);
+
+        // Two equal bins put the peak halfway between them.
+        let features = analyzer().process(&spectrum(&[(440, 1.0), (441, 1.0)]));
+        assert_close(features.dominant_freq, 440.5);
+    }
+
+    #[test]
+    fn dominant_frequency_ignores_peaks_outside_the_range() {
+        let features = analyzer().process(&spectrum(&[(10, 5.0), (1010, 5.0), (300, 1.0)]));
+        assert_close(features.dominant_freq, 300.0);
+    }
+
+    #[test]
+    fn names_the_nearest_note() {
+        assert_eq!(note_name(440.0).as_deref(), Some("A4"));
+        assert_eq!(note_name(261.63).as_deref(), Some("C4"));
+        assert_eq!(note_name(27.5).as_deref(), Some("A0"));
+        assert_eq!(note_name(452.0).as_deref(), Some("A4"));
+        assert_eq!(note_name(460.0).as_deref(), Some("A#4"));
+        assert_eq!(note_name(0.0), None);
+
+        let features = analyzer().process(&spectrum(&[(439, 0.5), (440, 1.0), (441, 0.5)]));
+        assert_eq!(features.dominant_note.as_deref(), Some("A4"));
+    }
+
+    #[test]
+    fn chroma_folds_octaves_and_scales_the_strongest_class_to_one() {
+        // A3 and A4 add up in the A class, C4 is a fifth of that energy.
+        let features = analyzer().process(&spectrum(&[(220, 1.0), (440, 2.0), (262, 1.0)]));
+        assert_eq!(features.chroma.len(), 12);
+        assert_close(features.chroma[9], 1.0);
+        assert_close(features.chroma[0], 0.2);
+        let others: f32 = features
+            .chroma
+            .iter()
+            .enumerate()
+            .filter(|(class, _)| *class != 0 && *class != 9)
+            .map(|(_, v)| v)
+            .sum();
+        assert_close(others, 0.0);
+    }
+
+    #[test]
+    fn chroma_ignores_bins_below_its_range() {
+        let features = analyzer().process(&spectrum(&[(30, 10.0), (440, 1.0)]));
+        assert_close(features.chroma[9], 1.0);
+        assert_close(features.chroma.iter().sum(), 1.0);
+    }
+
+    #[test]
+    fn flux_ignores_loudness_changes() {
+        let mut analyzer = analyzer();
+        analyzer.process(&spectrum(&[(100, 1.0), (300, 1.0)]));
+        let louder = analyzer.process(&spectrum(&[(100, 4.0), (300, 4.0)]));
+        assert_close(louder.flux, 0.0);
+
+        let moved = analyzer.process(&spectrum(&[(200, 1.0), (300, 1.0)]));
+        assert_close(moved.flux, 0.5);
+    }
+
+    #[test]
+    fn silence_yields_empty_features() {
+        let features = analyzer().process(&vec![0.0; NUM_BINS]);
+        assert_eq!(features.chroma, vec![0.0; 12]);
+        assert_eq!(features.dominant_note, None);
+        assert_close(features.centroid, 0.0);
+    }
+}
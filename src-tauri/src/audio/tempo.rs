@@ -0,0 +1,360 @@
+use std::collections::VecDeque;
+
+const MIN_BPM: f32 = 60.0;
+const MAX_BPM: f32 = 200.0;
+// Log-normal tempo prior (in octaves) that resolves half/double tempo ambiguity.
+const PRIOR_CENTER_BPM: f32 = 120.0;
+const PRIOR_WIDTH_OCTAVES: f32 = 1.0;
+// Seconds of novelty history used for the autocorrelation.
+const HISTORY_SECONDS: f32 = 8.0;
+// Minimum amount of non-silent history before a first estimate is made.
+const MIN_HISTORY_SECONDS: f32 = 3.0;
+const UPDATE_INTERVAL_SECONDS: f32 = 0.5;
+// Novelty below this over the last second counts as a break in the music.
+const SILENCE_NOVELTY: f32 = 1e-3;
+// How quickly confidence fades while the music is paused.
+const CONFIDENCE_HALF_LIFE_SECONDS: f32 = 10.0;
+// Relative deviation within which a new estimate refines the current tempo.
+const TEMPO_TOLERANCE: f32 = 0.08;
+// Consecutive disagreeing estimates needed before jumping to a new tempo.
+const TEMPO_CHANGE_VOTES: usize = 3;
+const BEATS_FOR_PHASE: usize = 4;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TempoResult {
+    /// Current tempo estimate, 0.0 until the tracker has locked on.
+    pub bpm: f32,
+    /// Position within the current beat, 0.0 on the beat, in [0, 1).
+    pub beat_phase: f32,
+    pub confidence: f32,
+}
+
+/// Tracks tempo and beat phase from the onset novelty curve.
+pub struct TempoTracker {
+    frame_rate: f32,
+    novelty: VecDeque<f32>,
+    capacity: usize,
+    active_frames: usize,
+    frames_until_update: usize,
+    update_interval: usize,
+    bpm: f32,
+    phase: f32,
+    confidence: f32,
+    confidence_decay: f32,
+    pending_bpm: f32,
+    pending_votes: usize,
+}
+
+impl TempoTracker {
+    pub fn new(frame_rate: f32) -> Self {
+        let frame_rate = frame_rate.max(1.0);
+        let capacity = (HISTORY_SECONDS * frame_rate).round() as usize;
+        let update_interval = ((UPDATE_INTERVAL_SECONDS * frame_rate).round() as usize).max(1);
+        Self {
+            frame_rate,
+            novelty: VecDeque::with_capacity(capacity),
+            capacity,
+            active_frames: 0,
+            frames_until_update: update_interval,
+            update_interval,
+            bpm: 0.0,
+            phase: 0.0,
+            confidence: 0.0,
+            confidence_decay: 0.5f32.powf(1.0 / (CONFIDENCE_HALF_LIFE_SECONDS * frame_rate)),
+            pending_bpm: 0.0,
+            pending_votes: 0,
+        }
+    }
+
+    pub fn process(&mut self, novelty: f32) -> TempoResult {
+        if self.novelty.len() == self.capacity {
+            self.novelty.pop_front();
+        }
+        self.novelty.push_back(novelty);
+
+        if self.bpm > 0.0 {
+            self.phase = (self.phase + self.bpm / 60.0 / self.frame_rate).fract();
+        }
+
+        let recent = self.frame_rate.round() as usize;
+        let is_silent = self
+            .novelty
+            .iter()
+            .rev()
+            .take(recent)
+            .all(|&v| v < SILENCE_NOVELTY);
+
+        if is_silent {
+            // Hold tempo and keep the phase running through breaks, but lose confidence.
+            self.active_frames = 0;
+            self.confidence *= self.confidence_decay;
+        } else {
+            self.active_frames += 1;
+            self.frames_until_update = self.frames_until_update.saturating_sub(1);
+            let min_history = (MIN_HISTORY_SECONDS * self.frame_rate) as usize;
+            if self.frames_until_update == 0 && self.active_frames >= min_history {
+                self.frames_until_update = self.update_interval;
+                self.update_estimate();
+            }
+        }
+
+        TempoResult {
+            bpm: self.bpm,
+            beat_phase: self.phase,
+            confidence: self.confidence,
+        }
+    }
+
+    fn update_estimate(&mut self) {
+        // Only use history recorded since the music (re)started.
+        let len = self.active_frames.min(self.novelty.len());
+        let history: Vec<f32> = self
+            .novelty
+            .iter()
+            .skip(self.novelty.len() - len)
+            .copied()
+            .collect();
+        let Some((candidate, confidence)) = estimate_tempo(&history, self.frame_rate) else {
+            return;
+        };
+
+        if self.bpm == 0.0 || (candidate - self.bpm).abs() / self.bpm < TEMPO_TOLERANCE {
+            self.bpm = if self.bpm == 0.0 {
+                candidate
+            } else {
+                self.bpm + (candidate - self.bpm) * 0.3
+            };
+            self.pending_votes = 0;
+        } else if self.pending_votes > 0
+            && (candidate - self.pending_bpm).abs() / self.pending_bpm < TEMPO_TOLERANCE
+        {
+            self.pending_votes += 1;
+            if self.pending_votes >= TEMPO_CHANGE_VOTES {
+                self.bpm = candidate;
+                self.pending_votes = 0;
+            }
+        } else {
+            self.pending_bpm = candidate;
+            self.pending_votes = 1;
+        }
+        self.confidence = self.confidence + (confidence - self.confidence) * 0.5;
+
+        let period = 60.0 * self.frame_rate / self.bpm;
+        let estimated_phase = estimate_phase(&history, period);
+        let mut diff = estimated_phase - self.phase;
+        diff -= diff.round();
+        self.phase = (self.phase + diff * 0.5).rem_euclid(1.0);
+    }
+}
+
+fn tempo_prior(bpm: f32) -> f32 {
+    let octaves = (bpm / PRIOR_CENTER_BPM).log2() / PRIOR_WIDTH_OCTAVES;
+    (-0.5 * octaves * octaves).exp()
+}
+
+/// Returns `(bpm, confidence)` from the autocorrelation of the novelty curve.
+fn estimate_tempo(novelty: &[f32], frame_rate: f32) -> Option<(f32, f32)> {
+    let n = novelty.len();
+    // A light smoothing keeps beats whose period is not a whole number of frames
+    // from splitting their energy across neighbouring lags.
+    let smoothed: Vec<f32> = (0..n)
+        .map(|i| {
+            let previous = novelty[i.saturating_sub(1)];
+            let next = novelty[(i + 1).min(n - 1)];
+            0.25 * previous + 0.5 * novelty[i] + 0.25 * next
+        })
+        .collect();
+    let mean = smoothed.iter().sum::<f32>() / n as f32;
+    let centered: Vec<f32> = smoothed.iter().map(|v| v - mean).collect();
+
+    let autocorrelation = |lag: usize| -> f32 {
+        if lag >= n {
+            return 0.0;
+        }
+        let sum: f32 = (lag..n).map(|i| centered[i] * centered[i - lag]).sum();
+        sum / (n - lag) as f32
+    };
+
+    let energy = autocorrelation(0);
+    if energy <= f32::EPSILON {
+        return None;
+    }
+
+    let min_lag = ((60.0 * frame_rate / MAX_BPM).floor() as usize).max(1);
+    let max_lag = ((60.0 * frame_rate / MIN_BPM).ceil() as usize).min(n.saturating_sub(2));
+    if max_lag <= min_lag + 1 {
+        return None;
+    }
+
+    let scores: Vec<f32> = (min_lag - 1..=max_lag + 1)
+        .map(|lag| autocorrelation(lag) * tempo_prior(60.0 * frame_rate / lag.max(1) as f32))
+        .collect();
+    let (peak, _) = scores[1..scores.len() - 1]
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))?;
+    let peak = peak + 1;
+
+    // Parabolic interpolation for sub-frame lag resolution.
+    let (left, center, right) = (scores[peak - 1], scores[peak], scores[peak + 1]);
+    let denominator = left - 2.0 * center + right;
+    let offset = if denominator.abs() > f32::EPSILON {
+        (0.5 * (left - right) / denominator).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+    let lag = (min_lag - 1 + peak) as f32 + offset;
+    let bpm = 60.0 * frame_rate / lag;
+    let confidence = (autocorrelation(min_lag - 1 + peak) / energy).clamp(0.0, 1.0);
+    Some((bpm, confidence))
+}
+
+/// Aligns a comb of beat-spaced taps with the most recent novelty to find the phase.
+fn estimate_phase(novelty: &[f32], period: f32) -> f32 {
+    let n = novelty.len();
+    let sample = |position: f32| -> f32 {
+        if position < 0.0 {
+            return 0.0;
+        }
+        let index = position.floor() as usize;
+        let fraction = position - index as f32;
+        let a = novelty.get(index).copied().unwrap_or(0.0);
+        let b = novelty.get(index + 1).copied().unwrap_or(a);
+        a + (b - a) * fraction
+    };
+
+    let mut best_offset = 0;
+    let mut best_score = f32::MIN;
+    for offset in 0..period.ceil() as usize {
+        let score: f32 = (0..BEATS_FOR_PHASE)
+            .map(|k| sample((n - 1) as f32 - offset as f32 - k as f32 * period))
+            .sum();
+        if score > best_score {
+            best_score = score;
+            best_offset = offset;
+        }
+    }
+    (best_offset as f32 / period).fract()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{AudioProcessor, DspSettings};
+
+    const SAMPLE_RATE: u32 = 44100;
+    const FFT_SIZE: usize = 1024;
+
+    fn click_track(bpm: f32, seconds: f32) -> Vec<f32> {
+        let total = (seconds * SAMPLE_RATE as f32) as usize;
+        let interval = 60.0 / bpm * SAMPLE_RATE as f32;
+        let click_len = SAMPLE_RATE as usize / 50;
+        let mut samples = vec![0.0; total];
+        let mut next_click = 0.0f32;
+        while (next_click as usize) < total {
+            let start = next_click as usize;
+            for i in 0..click_len.min(total - start) {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let envelope = (-t * 200.0).exp();
+                samples[start + i] = envelope * (2.0 * std::f32::consts::PI * 80.0 * t).sin();
+            }
+            next_click += interval;
+        }
+        samples
+    }
+
+    struct Chain {
+        processor: AudioProcessor,
+        settings: DspSettings,
+    }
+
+    impl Chain {
+        fn new() -> Self {
+            let settings = DspSettings {
+                fft_size: FFT_SIZE as u32,
+                sample_rate: None,
+                ..Default::default()
+            };
+            Self {
+                processor: AudioProcessor::new(&settings, SAMPLE_RATE),
+                settings,
+            }
+        }
+
+        /// Runs samples through the full DSP chain and returns the latest tempo state.
+        fn run(&mut self, samples: &[f32]) -> TempoResult {
+            let mut result = TempoResult::default();
+            for chunk in samples.chunks(512) {
+                if let Some(analysis) =
+                    self.processor
+                        .process(chunk, 1, SAMPLE_RATE, &self.settings)
+                {
+                    result = TempoResult {
+                        bpm: analysis.bpm,
+                        beat_phase: analysis.beat_phase,
+                        confidence: analysis.bpm_confidence,
+                    };
+                }
+            }
+            result
+        }
+    }
+
+    #[test]
+    fn locks_onto_click_track_tempo() {
+        for bpm in [90.0, 120.0, 140.0] {
+            let result = Chain::new().run(&click_track(bpm, 10.0));
+            assert!(
+                (result.bpm - bpm).abs() < 2.0,
+                "expected {} BPM, got {}",
+                bpm,
+                result.bpm
+            );
+            assert!(
+                result.confidence > 0.3,
+                "low confidence {}",
+                result.confidence
+            );
+        }
+    }
+
+    #[test]
+    fn reports_nothing_without_music() {
+        let result = Chain::new().run(&vec![0.0; SAMPLE_RATE as usize * 5]);
+        assert_eq!(result.bpm, 0.0);
+        assert_eq!(result.confidence, 0.0);
+    }
+
+    #[test]
+    fn holds_tempo_through_short_breaks() {
+        let mut chain = Chain::new();
+        chain.run(&click_track(128.0, 10.0));
+        let result = chain.run(&vec![0.0; SAMPLE_RATE as usize * 3]);
+        assert!(
+            (result.bpm - 128.0).abs() < 2.0,
+            "lost tempo: {}",
+            result.bpm
+        );
+        assert!(result.confidence > 0.0);
+    }
+
+    #[test]
+    fn beat_phase_follows_clicks() {
+        let bpm = 120.0;
+        let samples = click_track(bpm, 10.0);
+        let frames = samples.len() / FFT_SIZE;
+        let result = Chain::new().run(&samples);
+
+        // The last analysed frame ends at this time; clicks land every 0.5 s from t = 0.
+        let end_time = (frames * FFT_SIZE) as f32 / SAMPLE_RATE as f32;
+        let expected = (end_time * bpm / 60.0).fract();
+        let mut diff = result.beat_phase - expected;
+        diff -= diff.round();
+        assert!(
+            diff.abs() < 0.2,
+            "phase {} too far from expected {}",
+            result.beat_phase,
+            expected
+        );
+    }
+}
@@ -113,9 +113,9 @@ impl Effect for BladePower {
         }
 
         let power = match self.config.frequency_range.as_str() {
-            "Mids" => mids_power(&audio_data.melbanks),
-            "High" => highs_power(&audio_data.melbanks),
-            _ => lows_power(&audio_data.melbanks),
+            "Mids" => mids_power(audio_data),
+            "High" => highs_power(audio_data),
+            _ => lows_power(audio_data),
         };
 
         let bar_level = (power * self.config.multiplier * 2.0).min(1.0);
@@ -1,5 +1,6 @@
+use super::idle::IdleSettings;
 use super::state::{EngineStateTx, PlaybackState, PresetCollection};
-use crate::audio::DspSettings;
+use crate::audio::{AudioInput, DspSettings};
 use crate::engine::generated::EffectConfig;
 use crate::store::Scene;
 use crate::types::{Device, Virtual};
@@ -47,6 +48,14 @@ pub enum EngineCommand {
     DeleteScene(String),
     ActivateScene(String),
     SetApiPort(u16),
+    UpdateIdleSettings(IdleSettings),
+    SetAudioInput {
+        name: String,
+        input: AudioInput,
+    },
+    RemoveAudioInput {
+        name: String,
+    },
 }
 
 pub struct EngineCommandTx(pub mpsc::Sender<EngineCommand>);
@@ -295,6 +304,40 @@ pub fn set_api_port(port: u16, command_tx: State<EngineCommandTx>) -> Result<(),
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+#[specta]
+pub fn update_idle_settings(
+    settings: IdleSettings,
+    command_tx: State<EngineCommandTx>,
+) -> Result<(), String> {
+    command_tx
+        .0
+        .send(EngineCommand::UpdateIdleSettings(settings))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta]
+pub fn set_audio_input(
+    name: String,
+    input: AudioInput,
+    command_tx: State<EngineCommandTx>,
+) -> Result<(), String> {
+    command_tx
+        .0
+        .send(EngineCommand::SetAudioInput { name, input })
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta]
+pub fn remove_audio_input(name: String, command_tx: State<EngineCommandTx>) -> Result<(), String> {
+    command_tx
+        .0
+        .send(EngineCommand::RemoveAudioInput { name })
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 #[specta]
 pub fn trigger_reload(command_tx: State<EngineCommandTx>) -> Result<(), String> {
@@ -5,7 +5,7 @@ use super::generated::{
 };
 use super::state::{ActiveEffectsState, ActiveVirtual, PlaybackState};
 use crate::api::ApiCommand;
-use crate::audio::AudioCommand;
+use crate::audio::{AudioChannel, AudioCommand};
 use crate::store::{self, EngineState, Scene, SceneEffect};
 use crate::types::{Device, MatrixCell, Virtual};
 use std::collections::HashMap;
@@ -35,6 +35,36 @@ fn emit_active_effects_update(state: &ActiveEffectsState, app_handle: &AppHandle
     app_handle.emit("scene-activated", state).unwrap();
 }
 
+/// Looks up the effect config of a scene entry, preferring user presets over built-in ones.
+pub fn resolve_scene_effect(
+    engine_state: &EngineState,
+    scene_effect: &SceneEffect,
+) -> Option<EffectConfig> {
+    match scene_effect {
+        SceneEffect::Custom(config) => Some(config.clone()),
+        SceneEffect::Preset(scene_preset) => engine_state
+            .effect_presets
+            .get(&scene_preset.effect_id)
+            .and_then(|presets| presets.get(&scene_preset.preset_name))
+            .cloned()
+            .or_else(|| {
+                get_built_in_presets_for_effect(&scene_preset.effect_id)
+                    .get(&scene_preset.preset_name)
+                    .cloned()
+            }),
+    }
+}
+
+/// Hands every configured additional audio input to the audio thread.
+pub fn start_audio_inputs(engine_state: &EngineState, audio_command_tx: &Sender<AudioCommand>) {
+    for (name, input) in &engine_state.audio_inputs {
+        let _ = audio_command_tx.send(AudioCommand::SetInput {
+            name: name.clone(),
+            input: input.clone(),
+        });
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn handle_command(
     command: EngineCommand,
@@ -67,7 +97,14 @@ pub fn handle_command(
         }
         EngineCommand::ReloadState => {
             println!("[ENGINE] Reloading state from disk.");
+            let previous_inputs = std::mem::take(&mut engine_state.audio_inputs);
             *engine_state = store::load_engine_state(app_handle);
+            for name in previous_inputs.keys() {
+                if !engine_state.audio_inputs.contains_key(name) {
+                    let _ = audio_command_tx.send(AudioCommand::RemoveInput(name.clone()));
+                }
+            }
+            start_audio_inputs(engine_state, audio_command_tx);
             *virtuals = engine_state
                 .virtuals
                 .clone()
@@ -121,6 +158,8 @@ pub fn handle_command(
                 name: config.name.clone(),
                 matrix_data,
                 is_device: Some(device_ip.clone()),
+                audio_channel: AudioChannel::Mix,
+                audio_source: None,
             };
             let pixel_count = device_virtual
                 .matrix_data
@@ -231,6 +270,38 @@ pub fn handle_command(
             }
         }
         EngineCommand::SetTargetFps { .. } => { /* Handled in main loop */ }
+        EngineCommand::UpdateIdleSettings(settings) => {
+            println!("[ENGINE] Updating idle settings: {:?}", settings.action);
+            engine_state.idle_settings = settings;
+            app_handle
+                .emit("idle-settings-changed", &engine_state.idle_settings)
+                .unwrap();
+            should_save_state = true;
+        }
+        EngineCommand::SetAudioInput { name, input } => {
+            println!(
+                "[ENGINE] Setting audio input '{}' to {}",
+                name, input.device_name
+            );
+            engine_state
+                .audio_inputs
+                .insert(name.clone(), input.clone());
+            let _ = audio_command_tx.send(AudioCommand::SetInput { name, input });
+            app_handle
+                .emit("audio-inputs-changed", &engine_state.audio_inputs)
+                .unwrap();
+            should_save_state = true;
+        }
+        EngineCommand::RemoveAudioInput { name } => {
+            println!("[ENGINE] Removing audio input '{}'", name);
+            if engine_state.audio_inputs.remove(&name).is_some() {
+                should_save_state = true;
+            }
+            let _ = audio_command_tx.send(AudioCommand::RemoveInput(name));
+            app_handle
+                .emit("audio-inputs-changed", &engine_state.audio_inputs)
+                .unwrap();
+        }
         EngineCommand::SaveScene(scene) => {
             println!("[ENGINE] Saving scene '{}' ({})", scene.name, scene.id);
             engine_state.scenes.insert(scene.id.clone(), scene);
@@ -256,20 +327,7 @@ pub fn handle_command(
                 }
                 for (virtual_id, scene_effect) in &scene.virtual_effects {
                     if let Some(active_virtual) = virtuals.get_mut(virtual_id) {
-                        let effect_config = match scene_effect {
-                            SceneEffect::Custom(config) => Some(config.clone()),
-                            SceneEffect::Preset(scene_preset) => engine_state
-                                .effect_presets
-                                .get(&scene_preset.effect_id)
-                                .and_then(|presets| presets.get(&scene_preset.preset_name))
-                                .cloned()
-                                .or_else(|| {
-                                    get_built_in_presets_for_effect(&scene_preset.effect_id)
-                                        .get(&scene_preset.preset_name)
-                                        .cloned()
-                                }),
-                        };
-                        if let Some(config) = effect_config {
+                        if let Some(config) = resolve_scene_effect(engine_state, scene_effect) {
                             let effect_id = get_effect_id_from_config(&config);
                             new_selected_effects.insert(virtual_id.clone(), effect_id.clone());
                             new_effect_settings
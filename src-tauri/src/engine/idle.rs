@@ -0,0 +1,165 @@
+use super::generated::create_effect;
+use super::handler::resolve_scene_effect;
+use super::state::ActiveVirtual;
+use crate::effects::Effect;
+use crate::store::EngineState;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter};
+
+/// What the engine does once the audio has been silent for a while.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, Default, PartialEq)]
+pub enum IdleAction {
+    #[default]
+    None,
+    FadeToBlack,
+    /// Show the scene with this id until audio returns, then restore the previous effects.
+    Scene(String),
+}
+
+#[derive(Serialize, Deserialize, Type, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct IdleSettings {
+    pub action: IdleAction,
+    /// Seconds of silence (see the noise gate in `DspSettings`) before going idle.
+    pub after_seconds: f32,
+    pub fade_seconds: f32,
+}
+
+impl Default for IdleSettings {
+    fn default() -> Self {
+        Self {
+            action: IdleAction::None,
+            after_seconds: 10.0,
+            fade_seconds: 2.0,
+        }
+    }
+}
+
+#[derive(Serialize, Type, Clone)]
+pub struct IdleState {
+    pub is_idle: bool,
+}
+
+/// Tracks silence and drives the idle fade or idle scene.
+pub struct IdleController {
+    is_idle: bool,
+    /// Set when the user interrupts idle mode; cleared once audio returns.
+    suppressed: bool,
+    brightness: f32,
+    stashed_effects: Option<HashMap<String, Option<Box<dyn Effect>>>>,
+}
+
+impl IdleController {
+    pub fn new() -> Self {
+        Self {
+            is_idle: false,
+            suppressed: false,
+            brightness: 1.0,
+            stashed_effects: None,
+        }
+    }
+
+    /// Output brightness multiplier for the current frame.
+    pub fn brightness(&self) -> f32 {
+        self.brightness
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn update(
+        &mut self,
+        settings: &IdleSettings,
+        silence: bool,
+        silence_duration: f32,
+        frame_seconds: f32,
+        engine_state: &EngineState,
+        virtuals: &mut HashMap<String, ActiveVirtual>,
+        app_handle: &AppHandle,
+    ) {
+        if !silence {
+            self.suppressed = false;
+        }
+        let should_idle = settings.action != IdleAction::None
+            && !self.suppressed
+            && silence
+            && silence_duration >= settings.after_seconds;
+
+        if should_idle && !self.is_idle {
+            println!(
+                "[ENGINE] {:.1}s of silence, going idle ({:?}).",
+                silence_duration, settings.action
+            );
+            self.is_idle = true;
+            if let IdleAction::Scene(scene_id) = &settings.action {
+                self.show_scene(scene_id, engine_state, virtuals);
+            }
+            let _ = app_handle.emit("idle-state-changed", &IdleState { is_idle: true });
+        } else if !should_idle && self.is_idle {
+            println!("[ENGINE] Leaving idle mode.");
+            self.wake(virtuals, app_handle);
+        }
+
+        let target = if self.is_idle && settings.action == IdleAction::FadeToBlack {
+            0.0
+        } else {
+            1.0
+        };
+        if settings.fade_seconds <= 0.0 {
+            self.brightness = target;
+        } else {
+            let step = frame_seconds / settings.fade_seconds;
+            self.brightness += (target - self.brightness).clamp(-step, step);
+        }
+    }
+
+    /// Leaves idle mode right away, e.g. because the user picked another effect.
+    /// Idle mode stays off until audio has returned at least once.
+    pub fn interrupt(
+        &mut self,
+        virtuals: &mut HashMap<String, ActiveVirtual>,
+        app_handle: &AppHandle,
+    ) {
+        if self.is_idle {
+            println!("[ENGINE] Idle mode interrupted by user.");
+            self.suppressed = true;
+            self.wake(virtuals, app_handle);
+        }
+    }
+
+    fn show_scene(
+        &mut self,
+        scene_id: &str,
+        engine_state: &EngineState,
+        virtuals: &mut HashMap<String, ActiveVirtual>,
+    ) {
+        let Some(scene) = engine_state.scenes.get(scene_id) else {
+            eprintln!("[ENGINE] Idle scene '{}' not found.", scene_id);
+            return;
+        };
+        self.stashed_effects = Some(
+            virtuals
+                .iter_mut()
+                .map(|(id, active_virtual)| (id.clone(), active_virtual.effect.take()))
+                .collect(),
+        );
+        for (virtual_id, scene_effect) in &scene.virtual_effects {
+            if let Some(active_virtual) = virtuals.get_mut(virtual_id) {
+                active_virtual.effect =
+                    resolve_scene_effect(engine_state, scene_effect).map(create_effect);
+            }
+        }
+    }
+
+    fn wake(&mut self, virtuals: &mut HashMap<String, ActiveVirtual>, app_handle: &AppHandle) {
+        self.is_idle = false;
+        if let Some(stashed) = self.stashed_effects.take() {
+            for (virtual_id, effect) in stashed {
+                if let Some(active_virtual) = virtuals.get_mut(&virtual_id) {
+                    active_virtual.effect = effect;
+                }
+            }
+        }
+        let _ = app_handle.emit("idle-state-changed", &IdleState { is_idle: false });
+    }
+}
@@ -1,17 +1,22 @@
 mod commands;
 pub mod generated;
 mod handler;
+mod idle;
+mod output;
 mod renderer;
 mod state;
 
 pub use commands::*;
 pub use generated::*;
+pub use idle::{IdleAction, IdleSettings, IdleState};
 pub use state::*;
 
 use crate::api::ApiCommand;
-use crate::audio::SharedAudioData;
+use crate::audio::{AudioChannel, SharedAudioData, SharedAudioSources};
 use crate::store;
 use crate::types::{MatrixCell, Virtual};
+use crate::utils::delay::DelayQueue;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::net::UdpSocket;
 use std::sync::mpsc::{self, Receiver, Sender};
@@ -23,11 +28,13 @@ pub fn run_effect_engine(
     command_rx: mpsc::Receiver<EngineCommand>,
     request_rx: Receiver<EngineRequest>,
     audio_data: State<SharedAudioData>,
+    audio_sources: State<SharedAudioSources>,
     audio_command_tx: Sender<crate::audio::AudioCommand>,
     api_command_tx: Sender<ApiCommand>,
     app_handle: AppHandle,
 ) {
     let mut engine_state = store::load_engine_state(&app_handle);
+    handler::start_audio_inputs(&engine_state, &audio_command_tx);
     let correct_api_port = engine_state.api_port;
     if let Err(e) = api_command_tx.send(ApiCommand::Restart {
         port: correct_api_port,
@@ -63,7 +70,7 @@ pub fn run_effect_engine(
 
     for (device_ip, device_config) in &devices {
         let virtual_id = format!("device_{}", device_ip);
-        if !virtuals.contains_key(&virtual_id) {
+        if let Entry::Vacant(slot) = virtuals.entry(virtual_id) {
             let matrix_data = vec![(0..device_config.led_count)
                 .map(|i| {
                     Some(MatrixCell {
@@ -73,10 +80,12 @@ pub fn run_effect_engine(
                 })
                 .collect()];
             let device_virtual = Virtual {
-                id: virtual_id.clone(),
+                id: slot.key().clone(),
                 name: device_config.name.clone(),
                 matrix_data,
                 is_device: Some(device_ip.clone()),
+                audio_channel: AudioChannel::Mix,
+                audio_source: None,
             };
             let pixel_count = device_virtual
                 .matrix_data
@@ -92,7 +101,7 @@ pub fn run_effect_engine(
                 g_channel: vec![0.0; pixel_count],
                 b_channel: vec![0.0; pixel_count],
             };
-            virtuals.insert(virtual_id, active_virtual);
+            slot.insert(active_virtual);
         }
     }
 
@@ -100,12 +109,18 @@ pub fn run_effect_engine(
     socket
         .set_nonblocking(true)
         .expect("Failed to set non-blocking socket");
+    let mut outputs = output::Outputs::new(socket);
     let mut frame_count: u8 = 0;
     let mut target_frame_duration = Duration::from_millis(1000 / 60);
     let mut is_paused = false;
+    let mut idle = idle::IdleController::new();
+    let mut last_frame_start = Instant::now();
+    let mut output_queue = DelayQueue::new();
 
     loop {
         let frame_start = Instant::now();
+        let frame_seconds = frame_start.duration_since(last_frame_start).as_secs_f32();
+        last_frame_start = frame_start;
 
         // --- START: THE FIX ---
         // Declare ONE flag at the top of the loop.
@@ -185,6 +200,16 @@ pub fn run_effect_engine(
                     target_frame_duration = Duration::from_millis(1000 / fps as u64);
                 }
             } else {
+                if matches!(
+                    command,
+                    EngineCommand::StartEffect { .. }
+                        | EngineCommand::StopEffect { .. }
+                        | EngineCommand::UpdateSettings { .. }
+                        | EngineCommand::ActivateScene(_)
+                        | EngineCommand::ReloadState
+                ) {
+                    idle.interrupt(&mut virtuals, &app_handle);
+                }
                 // The handler now correctly contributes to the single flag
                 should_save_state |= handler::handle_command(
                     command,
@@ -214,16 +239,42 @@ pub fn run_effect_engine(
         }
         // --- END: THE FIX ---
 
+        let (silence, silence_duration) = {
+            let data = audio_data.inner().0.lock().unwrap();
+            (data.silence, data.silence_duration)
+        };
+        idle.update(
+            &engine_state.idle_settings,
+            silence,
+            silence_duration,
+            frame_seconds,
+            &engine_state,
+            &mut virtuals,
+            &app_handle,
+        );
+
         if !is_paused {
             frame_count = frame_count.wrapping_add(1);
-            renderer::render_frame(
+            let device_buffers = renderer::render_frame(
                 &mut virtuals,
                 &audio_data,
+                &audio_sources,
                 &devices,
-                &socket,
-                frame_count,
+                idle.brightness(),
                 &app_handle,
             );
+            // A negative audio delay holds back the LED output instead of the analysis.
+            let output_delay = Duration::from_millis(
+                engine_state
+                    .dsp_settings
+                    .audio_delay_ms
+                    .min(0)
+                    .unsigned_abs() as u64,
+            );
+            output_queue.push(frame_start, device_buffers);
+            if let Some(device_buffers) = output_queue.pop_latest(Instant::now(), output_delay) {
+                outputs.send_frame(&devices, &device_buffers, frame_count);
+            }
         }
 
         let frame_duration = frame_start.elapsed();
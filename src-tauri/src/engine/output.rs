@@ -0,0 +1,88 @@
+use crate::types::{Device, OutputProtocol};
+use crate::utils::artnet::ArtNetSender;
+use crate::utils::ddp;
+use crate::utils::e131::E131Sender;
+use crate::utils::opc::OpcSender;
+use crate::utils::serial::SerialSender;
+use crate::utils::wled_udp::{self, WledUdpSettings};
+use std::collections::HashMap;
+use std::net::UdpSocket;
+
+/// Protocol state of one device, kept between frames.
+enum DeviceOutput {
+    Ddp,
+    E131(E131Sender),
+    ArtNet(ArtNetSender),
+    WledUdp(WledUdpSettings),
+    Opc(OpcSender),
+    Serial(SerialSender),
+}
+
+impl DeviceOutput {
+    fn new(ip: &str, protocol: &OutputProtocol) -> Self {
+        match protocol {
+            OutputProtocol::Ddp => DeviceOutput::Ddp,
+            OutputProtocol::E131(settings) => DeviceOutput::E131(E131Sender::new(settings.clone())),
+            OutputProtocol::ArtNet(settings) => {
+                DeviceOutput::ArtNet(ArtNetSender::new(settings.clone()))
+            }
+            OutputProtocol::WledUdp(settings) => DeviceOutput::WledUdp(settings.clone()),
+            OutputProtocol::Opc(settings) => DeviceOutput::Opc(OpcSender::new(ip, settings)),
+            OutputProtocol::Serial(settings) => DeviceOutput::Serial(SerialSender::new(settings)),
+        }
+    }
+}
+
+/// Sends the rendered device buffers, each with its device's output protocol.
+pub struct Outputs {
+    socket: UdpSocket,
+    /// By device IP, together with the protocol settings they were created for.
+    outputs: HashMap<String, (OutputProtocol, DeviceOutput)>,
+}
+
+impl Outputs {
+    pub fn new(socket: UdpSocket) -> Self {
+        Self {
+            socket,
+            outputs: HashMap::new(),
+        }
+    }
+
+    pub fn send_frame(
+        &mut self,
+        devices: &HashMap<String, Device>,
+        device_buffers: &HashMap<String, Vec<u8>>,
+        frame_count: u8,
+    ) {
+        self.outputs.retain(|ip, (protocol, _)| {
+            devices
+                .get(ip)
+                .is_some_and(|device| device.protocol == *protocol)
+        });
+
+        for (ip, buffer) in device_buffers {
+            let Some(device) = devices.get(ip) else {
+                continue;
+            };
+            let (_, output) = self.outputs.entry(ip.clone()).or_insert_with(|| {
+                (
+                    device.protocol.clone(),
+                    DeviceOutput::new(ip, &device.protocol),
+                )
+            });
+            let _ = match output {
+                DeviceOutput::Ddp => {
+                    let destination = format!("{}:4048", ip);
+                    ddp::send_ddp_packet(&self.socket, &destination, 0, buffer, frame_count)
+                }
+                DeviceOutput::E131(sender) => sender.send(&self.socket, ip, buffer),
+                DeviceOutput::ArtNet(sender) => sender.send(&self.socket, ip, buffer),
+                DeviceOutput::WledUdp(settings) => {
+                    wled_udp::send_wled_udp_packets(&self.socket, ip, settings, buffer)
+                }
+                DeviceOutput::Opc(sender) => sender.send(buffer),
+                DeviceOutput::Serial(sender) => sender.send(buffer),
+            };
+        }
+    }
+}
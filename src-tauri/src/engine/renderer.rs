@@ -1,27 +1,44 @@
 use super::state::ActiveVirtual;
-use crate::audio::SharedAudioData;
+use crate::audio::{AudioAnalysisData, AudioChannel, SharedAudioData, SharedAudioSources};
 use crate::types::Device;
-use crate::utils::{colors, ddp, dsp};
+use crate::utils::{colors, dsp};
 use std::collections::HashMap;
-use std::net::UdpSocket;
 use tauri::{AppHandle, Emitter, State};
 
+/// Renders every virtual, emits the previews and returns the pixel data per device IP.
 pub fn render_frame(
     virtuals: &mut HashMap<String, ActiveVirtual>,
     audio_data: &State<SharedAudioData>,
+    audio_sources: &State<SharedAudioSources>,
     devices: &HashMap<String, Device>,
-    socket: &UdpSocket,
-    frame_count: u8,
+    brightness: f32,
     app_handle: &AppHandle,
-) {
+) -> HashMap<String, Vec<u8>> {
     let latest_audio_data = audio_data.inner().0.lock().unwrap().clone();
+    let mut source_audio_data: HashMap<(Option<String>, AudioChannel), AudioAnalysisData> =
+        HashMap::new();
     let mut device_buffers: HashMap<String, Vec<u8>> = HashMap::new();
     let mut preview_frames: HashMap<String, Vec<u8>> = HashMap::new();
 
     for (virtual_id, active_virtual) in virtuals {
         if let Some(effect) = &mut active_virtual.effect {
             let mut virtual_frame = vec![0u8; active_virtual.pixel_count * 3];
-            effect.render(&latest_audio_data, &mut virtual_frame);
+            let source = &active_virtual.config.audio_source;
+            let channel = active_virtual.config.audio_channel;
+            let virtual_audio_data = if source.is_none() && channel == AudioChannel::Mix {
+                &latest_audio_data
+            } else {
+                source_audio_data
+                    .entry((source.clone(), channel))
+                    .or_insert_with(|| {
+                        let data = match source {
+                            Some(name) => audio_sources.get(name).unwrap_or_default(),
+                            None => latest_audio_data.clone(),
+                        };
+                        data.for_channel(channel)
+                    })
+            };
+            effect.render(virtual_audio_data, &mut virtual_frame);
             let base_config = effect.get_base_config();
             let pixel_count = active_virtual.pixel_count;
 
@@ -83,38 +100,38 @@ pub fn render_frame(
                     (active_virtual.b_channel[i] as u8).saturating_add(bg_color[2]);
             }
 
+            if brightness < 1.0 {
+                for value in virtual_frame.iter_mut() {
+                    *value = (*value as f32 * brightness) as u8;
+                }
+            }
+
             let mut linear_index = 0;
             for row in &active_virtual.config.matrix_data {
-                for cell in row {
-                    if let Some(cell_data) = cell {
-                        if let Some(device) = devices.get(&cell_data.device_id) {
-                            let device_buffer = device_buffers
-                                .entry(cell_data.device_id.clone())
-                                .or_insert_with(|| vec![0; device.led_count as usize * 3]);
-                            let source_idx = linear_index * 3;
-                            let dest_idx = cell_data.pixel as usize * 3;
-                            if dest_idx + 2 < device_buffer.len()
-                                && source_idx + 2 < virtual_frame.len()
-                            {
-                                device_buffer[dest_idx..dest_idx + 3]
-                                    .copy_from_slice(&virtual_frame[source_idx..source_idx + 3]);
-                            }
+                for cell_data in row.iter().flatten() {
+                    if let Some(device) = devices.get(&cell_data.device_id) {
+                        let device_buffer = device_buffers
+                            .entry(cell_data.device_id.clone())
+                            .or_insert_with(|| vec![0; device.led_count as usize * 3]);
+                        let source_idx = linear_index * 3;
+                        let dest_idx = cell_data.pixel as usize * 3;
+                        if dest_idx + 2 < device_buffer.len()
+                            && source_idx + 2 < virtual_frame.len()
+                        {
+                            device_buffer[dest_idx..dest_idx + 3]
+                                .copy_from_slice(&virtual_frame[source_idx..source_idx + 3]);
                         }
-                        linear_index += 1;
                     }
+                    linear_index += 1;
                 }
             }
             preview_frames.insert(virtual_id.clone(), virtual_frame);
         }
     }
 
-    for (ip, buffer) in &device_buffers {
-        let destination = format!("{}:4048", ip);
-        let _ = ddp::send_ddp_packet(socket, &destination, 0, buffer, frame_count);
-    }
-
     let preview_payload: HashMap<String, Vec<u8>> = preview_frames.into_iter().collect();
     if !preview_payload.is_empty() {
         app_handle.emit("engine-tick", &preview_payload).unwrap();
     }
+    device_buffers
 }
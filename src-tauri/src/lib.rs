@@ -43,12 +43,23 @@ fn configure_builder() -> Builder<tauri::Wry> {
             audio::get_audio_devices,
             audio::set_audio_device,
             audio::get_audio_analysis,
+            audio::get_audio_input_analysis,
             audio::get_dsp_settings,
+            audio::play_audio_file,
+            audio::seek_audio_file,
+            audio::set_audio_file_looping,
+            audio::replay_audio_analysis,
+            audio::recording::start_audio_recording,
+            audio::recording::stop_audio_recording,
+            audio::recording::get_audio_recording,
             engine::get_playback_state,
             engine::toggle_pause,
             store::export_settings,
             store::import_settings,
             engine::trigger_reload,
+            engine::update_idle_settings,
+            engine::set_audio_input,
+            engine::remove_audio_input,
             engine::update_dsp_settings,
             store::get_default_engine_state,
             engine::restart_audio_capture,
@@ -87,6 +98,9 @@ fn configure_builder() -> Builder<tauri::Wry> {
         .typ::<scan::ScanConfig>()
         .typ::<fire::FireConfig>()
         .typ::<audio::AudioDevicesInfo>()
+        .typ::<audio::AudioStreamError>()
+        .typ::<audio::AudioDeviceChange>()
+        .typ::<audio::AudioAnalysisUpdate>()
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -98,10 +112,13 @@ pub fn run() {
 
     let audio_data = audio::SharedAudioData::default();
     let dsp_settings = audio::SharedDspSettings::default();
+    let audio_sources = audio::SharedAudioSources::default();
+    let (analysis_tx, _) = tokio::sync::broadcast::channel::<audio::AudioAnalysisUpdate>(16);
 
     let initial_port = 3030;
     let api_manager_engine_command_tx = engine_command_tx.clone();
     let api_manager_engine_state_tx = engine_state_tx.clone();
+    let api_analysis_tx = analysis_tx.clone();
     thread::spawn(move || {
         let rt = tokio::runtime::Builder::new_multi_thread()
             .enable_all()
@@ -112,6 +129,7 @@ pub fn run() {
                 api_command_rx,
                 api_manager_engine_command_tx,
                 api_manager_engine_state_tx,
+                api_analysis_tx,
                 initial_port,
             )
             .await;
@@ -139,32 +157,50 @@ pub fn run() {
         .manage(api_command_tx.clone())
         .manage(audio_data.clone())
         .manage(dsp_settings.clone())
+        .manage(audio_sources.clone())
         .invoke_handler(builder.invoke_handler());
 
     tauri_builder = tauri_builder.setup(move |app| {
         builder.mount_events(app);
         let state_handle = app.handle().clone();
         let engine_handle = app.handle().clone();
+        let audio_handle = app.handle().clone();
+        let analysis_handle = app.handle().clone();
 
         let engine_api_command_tx = api_command_tx;
 
         thread::spawn(move || {
             let audio_data_state = state_handle.state::<audio::SharedAudioData>();
+            let audio_sources_state = state_handle.state::<audio::SharedAudioSources>();
             engine::run_effect_engine(
                 engine_command_rx,
                 engine_state_rx,
                 audio_data_state,
+                audio_sources_state,
                 audio_command_tx,
                 engine_api_command_tx,
                 engine_handle,
             );
         });
 
+        let publisher_audio_data = audio_data.0.clone();
+        let publisher_dsp_settings = dsp_settings.0.clone();
+        thread::spawn(move || {
+            audio::run_analysis_publisher(
+                publisher_audio_data,
+                publisher_dsp_settings,
+                analysis_tx,
+                analysis_handle,
+            );
+        });
+
         thread::spawn(move || {
             audio::start_audio_capture(
                 audio_command_rx,
                 audio_data.0.clone(),
                 dsp_settings.0.clone(),
+                audio_sources,
+                audio_handle,
             );
         });
 
@@ -1,5 +1,5 @@
-use crate::audio::DspSettings;
-use crate::engine::EffectConfig;
+use crate::audio::{AudioInput, DspSettings};
+use crate::engine::{EffectConfig, IdleSettings};
 use crate::presets::EffectPresetMap;
 use crate::types::{Device, Virtual};
 use serde::{Deserialize, Serialize};
@@ -43,6 +43,11 @@ pub struct EngineState {
     pub scenes: HashMap<String, Scene>,
     #[serde(default = "default_api_port")]
     pub api_port: u16,
+    #[serde(default)]
+    pub idle_settings: IdleSettings,
+    /// Additional capture streams by name, see `Virtual::audio_source`.
+    #[serde(default)]
+    pub audio_inputs: HashMap<String, AudioInput>,
 }
 
 fn default_api_port() -> u16 {
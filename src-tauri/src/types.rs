@@ -1,3 +1,9 @@
+use crate::audio::AudioChannel;
+use crate::utils::artnet::ArtNetSettings;
+use crate::utils::e131::E131Settings;
+use crate::utils::opc::OpcSettings;
+use crate::utils::serial::SerialSettings;
+use crate::utils::wled_udp::WledUdpSettings;
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
@@ -6,6 +12,25 @@ pub struct Device {
     pub ip_address: String,
     pub name: String,
     pub led_count: u32,
+    #[serde(default)]
+    pub protocol: OutputProtocol,
+}
+
+/// How the engine sends a device its pixels.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, Default, PartialEq)]
+#[serde(tag = "type", content = "config")]
+pub enum OutputProtocol {
+    /// DDP to port 4048, as used by WLED.
+    #[default]
+    Ddp,
+    E131(E131Settings),
+    ArtNet(ArtNetSettings),
+    /// WLED's own UDP realtime protocols, for firmwares that handle DDP badly.
+    WledUdp(WledUdpSettings),
+    /// Open Pixel Control over TCP, e.g. a Fadecandy server or an OPC simulator.
+    Opc(OpcSettings),
+    /// Adalight or TPM2 over a serial port. `ip_address` then only identifies the device.
+    Serial(SerialSettings),
 }
 
 #[derive(Serialize, Deserialize, Type, Clone, Debug)]
@@ -21,4 +46,12 @@ pub struct Virtual {
     pub matrix_data: Vec<Vec<Option<MatrixCell>>>,
     #[serde(default)]
     pub is_device: Option<String>,
+    /// Audio channel the virtual's effect reacts to. Anything but `Mix` needs
+    /// `DspSettings::channel_analysis` enabled, otherwise the mono mix is used.
+    #[serde(default)]
+    pub audio_channel: AudioChannel,
+    /// Name of the audio input (see `EngineState::audio_inputs`) driving this virtual.
+    /// `None` uses the main input; an unknown name renders as silence.
+    #[serde(default)]
+    pub audio_source: Option<String>,
 }
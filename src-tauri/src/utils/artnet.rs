@@ -0,0 +1,287 @@
+// src-tauri/src/utils/artnet.rs
+
+use super::dmx;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::net::UdpSocket;
+
+pub const ARTNET_PORT: u16 = 6454;
+const ARTNET_ID: &[u8; 8] = b"Art-Net\0";
+const OP_DMX: u16 = 0x5000;
+const OP_SYNC: u16 = 0x5200;
+const PROTOCOL_VERSION: u16 = 14;
+const DMX_HEADER_LEN: usize = 18;
+const MAX_PORT_ADDRESS: u16 = 0x7fff;
+
+/// Addressing and transport of an Art-Net device.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ArtNetSettings {
+    /// Net (0-127), subnet (0-15) and universe (0-15) of the first pixel.
+    /// Longer strips continue in the following universes, carrying over into
+    /// the next subnet and net.
+    pub net: u8,
+    pub subnet: u8,
+    pub universe: u8,
+    /// DMX channel of the first pixel within that universe (1-512).
+    pub start_channel: u16,
+    /// Channels used per universe before moving on to the next one. 510 keeps
+    /// RGB pixels from straddling two universes, 512 uses every channel.
+    pub channels_per_universe: u16,
+    /// Send an ArtSync after each frame, so the node outputs all universes at once.
+    pub sync: bool,
+    pub port: u16,
+}
+
+impl Default for ArtNetSettings {
+    fn default() -> Self {
+        Self {
+            net: 0,
+            subnet: 0,
+            universe: 0,
+            start_channel: 1,
+            channels_per_universe: 510,
+            sync: false,
+            port: ARTNET_PORT,
+        }
+    }
+}
+
+impl ArtNetSettings {
+    /// The 15-bit port address of the first universe.
+    pub fn port_address(&self) -> u16 {
+        ((self.net as u16 & 0x7f) << 8)
+            | ((self.subnet as u16 & 0x0f) << 4)
+            | (self.universe as u16 & 0x0f)
+    }
+}
+
+fn header(opcode: u16) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(DMX_HEADER_LEN + dmx::MAX_CHANNELS as usize);
+    packet.extend_from_slice(ARTNET_ID);
+    packet.extend_from_slice(&opcode.to_le_bytes());
+    packet.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+    packet
+}
+
+/// Builds an ArtDmx packet carrying `data` from DMX channel `first_channel` on.
+/// Channels before it are sent as zero, and the length is padded to be even.
+pub fn build_dmx_packet(
+    sequence: u8,
+    port_address: u16,
+    first_channel: u16,
+    data: &[u8],
+) -> Vec<u8> {
+    let slots = first_channel as usize - 1 + data.len();
+    let length = (slots + slots % 2).max(2);
+    let mut packet = header(OP_DMX);
+    packet.push(sequence);
+    packet.push(0); // Physical input port, informational only.
+    packet.extend_from_slice(&[(port_address & 0xff) as u8, (port_address >> 8) as u8]);
+    packet.extend_from_slice(&(length as u16).to_be_bytes());
+    packet.resize(DMX_HEADER_LEN + first_channel as usize - 1, 0);
+    packet.extend_from_slice(data);
+    packet.resize(DMX_HEADER_LEN + length, 0);
+    packet
+}
+
+pub fn build_sync_packet() -> Vec<u8> {
+    let mut packet = header(OP_SYNC);
+    packet.extend_from_slice(&[0, 0]);
+    packet
+}
+
+/// Sends a device's pixels as ArtDmx, keeping a sequence number per universe.
+pub struct ArtNetSender {
+    settings: ArtNetSettings,
+    sequences: HashMap<u16, u8>,
+}
+
+impl ArtNetSender {
+    pub fn new(settings: ArtNetSettings) -> Self {
+        Self {
+            settings,
+            sequences: HashMap::new(),
+        }
+    }
+
+    pub fn send(&mut self, socket: &UdpSocket, ip: &str, data: &[u8]) -> std::io::Result<()> {
+        let destination = (ip, self.settings.port);
+        let parts = dmx::map_universes(
+            data,
+            self.settings.port_address(),
+            MAX_PORT_ADDRESS,
+            self.settings.start_channel,
+            self.settings.channels_per_universe,
+        );
+        for (port_address, first_channel, part) in parts {
+            // 0 tells the node not to reorder packets, so the sequence runs 1-255.
+            let sequence = self.sequences.entry(port_address).or_insert(0);
+            *sequence = sequence.checked_add(1).unwrap_or(1);
+            let packet = build_dmx_packet(*sequence, port_address, first_channel, part);
+            socket.send_to(&packet, destination)?;
+        }
+        if self.settings.sync {
+            socket.send_to(&build_sync_packet(), destination)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn loopback_pair() -> (UdpSocket, UdpSocket) {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        (sender, receiver)
+    }
+
+    fn receive(receiver: &UdpSocket) -> Vec<u8> {
+        let mut buf = [0u8; 1500];
+        let len = receiver.recv(&mut buf).unwrap();
+        buf[..len].to_vec()
+    }
+
+    fn port_address(packet: &[u8]) -> u16 {
+        u16::from_le_bytes([packet[14], packet[15]])
+    }
+
+    fn length(packet: &[u8]) -> usize {
+        u16::from_be_bytes([packet[16], packet[17]]) as usize
+    }
+
+    fn dmx_data(packet: &[u8]) -> &[u8] {
+        &packet[DMX_HEADER_LEN..]
+    }
+
+    #[test]
+    fn sends_valid_art_dmx_packets() {
+        let (sender, receiver) = loopback_pair();
+        let settings = ArtNetSettings {
+            net: 1,
+            subnet: 2,
+            universe: 3,
+            port: receiver.local_addr().unwrap().port(),
+            ..Default::default()
+        };
+        let mut artnet = ArtNetSender::new(settings);
+        artnet.send(&sender, "127.0.0.1", &[10, 20, 30]).unwrap();
+
+        let packet = receive(&receiver);
+        assert_eq!(&packet[..8], b"Art-Net\0");
+        // OpCode is little-endian, the protocol version big-endian.
+        assert_eq!(packet[8..10], [0x00, 0x50]);
+        assert_eq!(packet[10..12], [0, 14]);
+        assert_eq!(packet[12], 1);
+        assert_eq!(packet[13], 0);
+        // SubUni (subnet and universe) comes before Net.
+        assert_eq!(packet[14..16], [0x23, 0x01]);
+        assert_eq!(port_address(&packet), 0x0123);
+        // Three channels are padded to an even length.
+        assert_eq!(length(&packet), 4);
+        assert_eq!(dmx_data(&packet), [10, 20, 30, 0]);
+    }
+
+    #[test]
+    fn pads_up_to_the_start_channel() {
+        let packet = build_dmx_packet(1, 0, 4, &[1, 2, 3]);
+        assert_eq!(length(&packet), 6);
+        assert_eq!(dmx_data(&packet), [0, 0, 0, 1, 2, 3]);
+
+        let packet = build_dmx_packet(1, 0, 1, &[]);
+        assert_eq!(length(&packet), 2);
+        assert_eq!(dmx_data(&packet), [0, 0]);
+    }
+
+    #[test]
+    fn sequence_wraps_past_zero() {
+        let (sender, receiver) = loopback_pair();
+        let settings = ArtNetSettings {
+            port: receiver.local_addr().unwrap().port(),
+            ..Default::default()
+        };
+        let mut artnet = ArtNetSender::new(settings);
+        let mut sequences = Vec::new();
+        for _ in 0..257 {
+            artnet.send(&sender, "127.0.0.1", &[0; 3]).unwrap();
+            sequences.push(receive(&receiver)[12]);
+        }
+        let expected: Vec<u8> = (1..=255).chain([1, 2]).collect();
+        assert_eq!(sequences, expected);
+    }
+
+    #[test]
+    fn sends_art_sync_after_the_frame() {
+        let (sender, receiver) = loopback_pair();
+        let settings = ArtNetSettings {
+            sync: true,
+            port: receiver.local_addr().unwrap().port(),
+            ..Default::default()
+        };
+        let mut artnet = ArtNetSender::new(settings);
+        // 200 RGB pixels span two universes.
+        artnet.send(&sender, "127.0.0.1", &[255; 600]).unwrap();
+
+        assert_eq!(port_address(&receive(&receiver)), 0);
+        assert_eq!(port_address(&receive(&receiver)), 1);
+        let sync = receive(&receiver);
+        assert_eq!(&sync[..8], b"Art-Net\0");
+        assert_eq!(sync[8..], [0x00, 0x52, 0, 14, 0, 0]);
+    }
+
+    #[test]
+    fn universes_carry_over_into_the_next_subnet_and_net() {
+        let (sender, receiver) = loopback_pair();
+        let settings = ArtNetSettings {
+            net: 0,
+            subnet: 15,
+            universe: 15,
+            port: receiver.local_addr().unwrap().port(),
+            ..Default::default()
+        };
+        let mut artnet = ArtNetSender::new(settings);
+        // 341 RGB pixels: two full universes and one pixel in a third.
+        let data: Vec<u8> = (0..1023).map(|i| (i % 251) as u8).collect();
+        artnet.send(&sender, "127.0.0.1", &data).unwrap();
+
+        let first = receive(&receiver);
+        assert_eq!(first[14..16], [0xff, 0x00]);
+        assert_eq!(length(&first), 510);
+        assert_eq!(dmx_data(&first), &data[..510]);
+
+        // Universe 15 of subnet 15 rolls over into net 1.
+        let second = receive(&receiver);
+        assert_eq!(second[14..16], [0x00, 0x01]);
+        assert_eq!(dmx_data(&second), &data[510..1020]);
+
+        let third = receive(&receiver);
+        assert_eq!(third[14..16], [0x01, 0x01]);
+        assert_eq!(dmx_data(&third), [data[1020], data[1021], data[1022], 0]);
+    }
+
+    #[test]
+    fn stops_at_the_last_port_address() {
+        let (sender, receiver) = loopback_pair();
+        let settings = ArtNetSettings {
+            net: 127,
+            subnet: 15,
+            universe: 15,
+            sync: true,
+            port: receiver.local_addr().unwrap().port(),
+            ..Default::default()
+        };
+        let mut artnet = ArtNetSender::new(settings);
+        artnet.send(&sender, "127.0.0.1", &[1; 1020]).unwrap();
+
+        assert_eq!(port_address(&receive(&receiver)), MAX_PORT_ADDRESS);
+        // The overflow is dropped, so the ArtSync follows right away.
+        assert_eq!(receive(&receiver)[8..10], [0x00, 0x52]);
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Holds values back until a fixed time after they were captured.
+///
+/// The delay is passed on every release instead of being stored, so changing it
+/// takes effect immediately for everything already queued.
+pub struct DelayQueue<T> {
+    queue: VecDeque<(Instant, T)>,
+}
+
+impl<T> Default for DelayQueue<T> {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl<T> DelayQueue<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, captured: Instant, value: T) {
+        self.queue.push_back((captured, value));
+    }
+
+    /// Removes every value captured at least `delay` before `now` and returns the newest of them.
+    pub fn pop_latest(&mut self, now: Instant, delay: Duration) -> Option<T> {
+        self.pop_merged(now, delay, |_, _| {})
+    }
+
+    /// Like `pop_latest`, but hands each value that is skipped over to `merge`
+    /// together with the newer value that replaces it.
+    pub fn pop_merged<F>(&mut self, now: Instant, delay: Duration, mut merge: F) -> Option<T>
+    where
+        F: FnMut(&mut T, T),
+    {
+        let mut latest = None;
+        while let Some((captured, _)) = self.queue.front() {
+            if now.saturating_duration_since(*captured) < delay {
+                break;
+            }
+            let Some((_, mut value)) = self.queue.pop_front() else {
+                break;
+            };
+            if let Some(earlier) = latest.take() {
+                merge(&mut value, earlier);
+            }
+            latest = Some(value);
+        }
+        latest
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn releases_values_once_their_delay_has_passed() {
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+        let delay = Duration::from_millis(100);
+        let mut queue = DelayQueue::new();
+        queue.push(ms(0), 1);
+        queue.push(ms(20), 2);
+        queue.push(ms(40), 3);
+
+        assert_eq!(queue.pop_latest(ms(99), delay), None);
+        assert_eq!(queue.pop_latest(ms(125), delay), Some(2));
+        assert_eq!(queue.len(), 1);
+        // Dropping the delay releases the rest right away.
+        assert_eq!(queue.pop_latest(ms(125), Duration::ZERO), Some(3));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn merges_skipped_values_into_the_newest() {
+        let start = Instant::now();
+        let mut queue = DelayQueue::new();
+        queue.push(start, vec![1]);
+        queue.push(start, vec![2]);
+        queue.push(start + Duration::from_millis(50), vec![3]);
+
+        let merged = queue.pop_merged(
+            start + Duration::from_millis(10),
+            Duration::from_millis(10),
+            |newer, earlier| {
+                newer.extend(earlier);
+            },
+        );
+        assert_eq!(merged, Some(vec![2, 1]));
+        assert_eq!(queue.len(), 1);
+    }
+}
@@ -0,0 +1,30 @@
+// src-tauri/src/utils/dmx.rs
+
+pub const MAX_CHANNELS: u16 = 512;
+
+/// Splits a device's channel data over consecutive universes, as
+/// `(universe, first channel, data)` parts. The first part starts at
+/// `start_channel`, later ones at channel 1. Data past `last_universe` is dropped.
+pub fn map_universes(
+    data: &[u8],
+    first_universe: u16,
+    last_universe: u16,
+    start_channel: u16,
+    channels_per_universe: u16,
+) -> Vec<(u16, u16, &[u8])> {
+    let channels_per_universe = channels_per_universe.clamp(1, MAX_CHANNELS);
+    let mut first_channel = start_channel.clamp(1, channels_per_universe);
+    let mut parts = Vec::new();
+    let mut offset = 0;
+    for universe in first_universe..=last_universe {
+        if offset >= data.len() {
+            break;
+        }
+        let capacity = (channels_per_universe - first_channel + 1) as usize;
+        let end = (offset + capacity).min(data.len());
+        parts.push((universe, first_channel, &data[offset..end]));
+        offset = end;
+        first_channel = 1;
+    }
+    parts
+}
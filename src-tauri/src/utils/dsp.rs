@@ -1,14 +1,14 @@
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
-#[derive(Serialize, Deserialize, Type, Clone, Debug)]
+#[derive(Serialize, Deserialize, Type, Clone, Debug, PartialEq)]
 pub struct BladePlusParams {
     pub log_base: f32,
     pub multiplier: f32,
     pub divisor: f32,
 }
 
-#[derive(Serialize, Deserialize, Type, Clone, Debug, Default)]
+#[derive(Serialize, Deserialize, Type, Clone, Debug, Default, PartialEq)]
 pub enum FilterbankType {
     #[default]
     Balanced,
@@ -18,6 +18,28 @@ pub enum FilterbankType {
     BladePlus(BladePlusParams),
 }
 
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug, Default, PartialEq)]
+pub enum WindowType {
+    #[default]
+    Hann,
+    Hamming,
+    BlackmanHarris,
+}
+
+/// Builds the analysis window applied to every FFT frame.
+pub fn generate_window(size: usize, window_type: WindowType) -> Vec<f32> {
+    if size < 2 {
+        return vec![1.0; size];
+    }
+    let window = match window_type {
+        WindowType::Hann => apodize::hanning_iter(size),
+        WindowType::Hamming => apodize::hamming_iter(size),
+        // apodize's "blackman" uses the 4-term Blackman-Harris coefficients.
+        WindowType::BlackmanHarris => apodize::blackman_iter(size),
+    };
+    window.map(|w| w as f32).collect()
+}
+
 fn hz_to_mel(hz: f32) -> f32 {
     2595.0 * (1.0 + hz / 700.0).log10()
 }
@@ -37,8 +59,10 @@ fn vocal_to_hz(vocal: f32) -> f32 {
     250.0 * (9.0f32.powf(vocal / 3340.0) - 1.0)
 }
 
+/// Builds triangular filters over the `num_bins` magnitude bins of the FFT
+/// (half the FFT size).
 pub fn generate_filterbank(
-    fft_size: usize,
+    num_bins: usize,
     sample_rate: u32,
     num_bands: usize,
     min_freq: f32,
@@ -49,7 +73,7 @@ pub fn generate_filterbank(
 
     let mut fft_bins: Vec<usize> = hz_points
         .into_iter()
-        .map(|hz| (hz * (fft_size as f32 / sample_rate as f32)).floor() as usize)
+        .map(|hz| (hz * (2 * num_bins) as f32 / sample_rate as f32).floor() as usize)
         .collect();
     for i in 1..fft_bins.len() {
         fft_bins[i] = fft_bins[i].max(fft_bins[i - 1] + 1);
@@ -76,6 +100,8 @@ pub fn generate_filterbank(
                 filter.push((k, weight));
             }
         }
+        // Bands reaching past Nyquist (max_freq above half the sample rate) are cut off there.
+        filter.retain(|&(k, _)| k < num_bins);
         filters.push(filter);
     }
 
@@ -140,8 +166,23 @@ pub fn calculate_center_frequencies(
     max_freq: f32,
     filter_type: FilterbankType,
 ) -> Result<Vec<f32>, String> {
-    let hz_points = get_hz_points(num_bands as usize, min_freq, max_freq, &filter_type);
-    Ok(hz_points[1..=num_bands as usize].to_vec())
+    Ok(center_frequencies(
+        num_bands as usize,
+        min_freq,
+        max_freq,
+        &filter_type,
+    ))
+}
+
+/// Center frequency (Hz) of every band of the filterbank.
+pub fn center_frequencies(
+    num_bands: usize,
+    min_freq: f32,
+    max_freq: f32,
+    filter_type: &FilterbankType,
+) -> Vec<f32> {
+    let hz_points = get_hz_points(num_bands, min_freq, max_freq, filter_type);
+    hz_points[1..=num_bands].to_vec()
 }
 
 // Gaussian blur implementation is unchanged
@@ -191,7 +232,7 @@ pub fn gaussian_blur_1d(data: &mut [f32], sigma: f32) {
 
     for i in 0..data_len {
         let mut sum = 0.0;
-        for k_idx in 0..kernel.len() {
+        for (k_idx, weight) in kernel.iter().enumerate() {
             let k_offset = k_idx as isize - kernel_radius;
             let data_idx = i + k_offset;
 
@@ -204,7 +245,7 @@ pub fn gaussian_blur_1d(data: &mut [f32], sigma: f32) {
                 data_idx
             } as usize;
 
-            sum += original[read_idx.min(data.len() - 1)] * kernel[k_idx];
+            sum += original[read_idx.min(data.len() - 1)] * weight;
         }
         data[i as usize] = sum;
     }
@@ -0,0 +1,244 @@
+// src-tauri/src/utils/e131.rs
+
+use super::dmx;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, UdpSocket};
+
+pub const E131_PORT: u16 = 5568;
+const SOURCE_NAME: &str = "ledfx-rust";
+const MAX_UNIVERSE: u16 = 63999;
+const HEADER_LEN: usize = 126;
+const ACN_IDENTIFIER: &[u8; 12] = b"ASC-E1.17\0\0\0";
+const VECTOR_ROOT_E131_DATA: u32 = 0x0000_0004;
+const VECTOR_E131_DATA_PACKET: u32 = 0x0000_0002;
+const VECTOR_DMP_SET_PROPERTY: u8 = 0x02;
+
+/// Component identifier of this sender, random per run (UUID v4 layout).
+static CID: Lazy<[u8; 16]> = Lazy::new(|| {
+    let mut cid: [u8; 16] = rand::random();
+    cid[6] = (cid[6] & 0x0f) | 0x40;
+    cid[8] = (cid[8] & 0x3f) | 0x80;
+    cid
+});
+
+/// Universe mapping and transport of an E1.31 (sACN) device.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct E131Settings {
+    /// Universe the first pixel is sent in (1-63999).
+    pub universe: u16,
+    /// DMX channel of the first pixel within that universe (1-512).
+    pub start_channel: u16,
+    /// Channels used per universe before moving on to the next one. 510 keeps
+    /// RGB pixels from straddling two universes, 512 uses every channel.
+    pub channels_per_universe: u16,
+    /// Receivers merge sources by priority (0-200, default 100).
+    pub priority: u8,
+    /// Send to the universe's multicast group instead of the device IP.
+    pub multicast: bool,
+    pub port: u16,
+}
+
+impl Default for E131Settings {
+    fn default() -> Self {
+        Self {
+            universe: 1,
+            start_channel: 1,
+            channels_per_universe: 510,
+            priority: 100,
+            multicast: false,
+            port: E131_PORT,
+        }
+    }
+}
+
+/// Multicast group of a universe, `239.255.<universe high>.<universe low>`.
+pub fn multicast_address(universe: u16) -> Ipv4Addr {
+    let [high, low] = universe.to_be_bytes();
+    Ipv4Addr::new(239, 255, high, low)
+}
+
+/// Builds an E1.31 data packet carrying `data` from DMX channel `first_channel` on.
+/// Channels before it are sent as zero.
+pub fn build_packet(
+    cid: &[u8; 16],
+    priority: u8,
+    sequence: u8,
+    universe: u16,
+    first_channel: u16,
+    data: &[u8],
+) -> Vec<u8> {
+    let slots = first_channel as usize - 1 + data.len();
+    let len = HEADER_LEN + slots;
+    let flags_and_length = |from: usize| (0x7000 | (len - from) as u16).to_be_bytes();
+
+    let mut packet = vec![0u8; len];
+    // Root layer
+    packet[0..2].copy_from_slice(&0x0010u16.to_be_bytes());
+    packet[4..16].copy_from_slice(ACN_IDENTIFIER);
+    packet[16..18].copy_from_slice(&flags_and_length(16));
+    packet[18..22].copy_from_slice(&VECTOR_ROOT_E131_DATA.to_be_bytes());
+    packet[22..38].copy_from_slice(cid);
+    // Framing layer
+    packet[38..40].copy_from_slice(&flags_and_length(38));
+    packet[40..44].copy_from_slice(&VECTOR_E131_DATA_PACKET.to_be_bytes());
+    let name = SOURCE_NAME.as_bytes();
+    packet[44..44 + name.len()].copy_from_slice(name);
+    packet[108] = priority.min(200);
+    packet[111] = sequence;
+    packet[113..115].copy_from_slice(&universe.to_be_bytes());
+    // DMP layer
+    packet[115..117].copy_from_slice(&flags_and_length(115));
+    packet[117] = VECTOR_DMP_SET_PROPERTY;
+    packet[118] = 0xa1;
+    packet[121..123].copy_from_slice(&1u16.to_be_bytes());
+    packet[123..125].copy_from_slice(&(slots as u16 + 1).to_be_bytes());
+    // packet[125] is the DMX start code, 0 for dimmer data.
+    packet[HEADER_LEN + first_channel as usize - 1..].copy_from_slice(data);
+    packet
+}
+
+/// Sends a device's pixels as E1.31, keeping a sequence number per universe.
+pub struct E131Sender {
+    settings: E131Settings,
+    sequences: HashMap<u16, u8>,
+}
+
+impl E131Sender {
+    pub fn new(settings: E131Settings) -> Self {
+        Self {
+            settings,
+            sequences: HashMap::new(),
+        }
+    }
+
+    pub fn send(&mut self, socket: &UdpSocket, ip: &str, data: &[u8]) -> std::io::Result<()> {
+        let parts = dmx::map_universes(
+            data,
+            self.settings.universe.clamp(1, MAX_UNIVERSE),
+            MAX_UNIVERSE,
+            self.settings.start_channel,
+            self.settings.channels_per_universe,
+        );
+        for (universe, first_channel, part) in parts {
+            let sequence = self.sequences.entry(universe).or_insert(0);
+            *sequence = sequence.wrapping_add(1);
+            let packet = build_packet(
+                &CID,
+                self.settings.priority,
+                *sequence,
+                universe,
+                first_channel,
+                part,
+            );
+            if self.settings.multicast {
+                socket.send_to(&packet, (multicast_address(universe), self.settings.port))?;
+            } else {
+                socket.send_to(&packet, (ip, self.settings.port))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn loopback_pair() -> (UdpSocket, UdpSocket) {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        (sender, receiver)
+    }
+
+    fn receive(receiver: &UdpSocket) -> Vec<u8> {
+        let mut buf = [0u8; 1500];
+        let len = receiver.recv(&mut buf).unwrap();
+        buf[..len].to_vec()
+    }
+
+    fn universe(packet: &[u8]) -> u16 {
+        u16::from_be_bytes([packet[113], packet[114]])
+    }
+
+    fn dmx_data(packet: &[u8]) -> &[u8] {
+        &packet[HEADER_LEN..]
+    }
+
+    #[test]
+    fn sends_valid_packets_across_universes() {
+        let (sender, receiver) = loopback_pair();
+        let settings = E131Settings {
+            universe: 5,
+            priority: 150,
+            port: receiver.local_addr().unwrap().port(),
+            ..Default::default()
+        };
+        let mut e131 = E131Sender::new(settings);
+        // 200 RGB pixels: 170 fit in 510 channels, 30 go to the next universe.
+        let data: Vec<u8> = (0..600).map(|i| (i % 251) as u8).collect();
+        e131.send(&sender, "127.0.0.1", &data).unwrap();
+
+        let first = receive(&receiver);
+        let second = receive(&receiver);
+        for packet in [&first, &second] {
+            assert_eq!(&packet[4..16], ACN_IDENTIFIER);
+            let root_len = u16::from_be_bytes([packet[16], packet[17]]);
+            assert_eq!(root_len, 0x7000 | (packet.len() - 16) as u16);
+            let dmp_len = u16::from_be_bytes([packet[115], packet[116]]);
+            assert_eq!(dmp_len, 0x7000 | (packet.len() - 115) as u16);
+            assert_eq!(&packet[22..38], &CID[..]);
+            assert_eq!(&packet[44..54], SOURCE_NAME.as_bytes());
+            assert_eq!(packet[108], 150);
+            assert_eq!(packet[111], 1);
+            let count = u16::from_be_bytes([packet[123], packet[124]]) as usize;
+            assert_eq!(count, packet.len() - HEADER_LEN + 1);
+            assert_eq!(packet[125], 0);
+        }
+        assert_eq!(universe(&first), 5);
+        assert_eq!(dmx_data(&first), &data[..510]);
+        assert_eq!(universe(&second), 6);
+        assert_eq!(dmx_data(&second), &data[510..]);
+
+        e131.send(&sender, "127.0.0.1", &data).unwrap();
+        assert_eq!(receive(&receiver)[111], 2);
+        assert_eq!(receive(&receiver)[111], 2);
+    }
+
+    #[test]
+    fn packs_full_universes_from_the_start_channel() {
+        let (sender, receiver) = loopback_pair();
+        let settings = E131Settings {
+            start_channel: 101,
+            channels_per_universe: 512,
+            port: receiver.local_addr().unwrap().port(),
+            ..Default::default()
+        };
+        let data = vec![7u8; 600];
+        E131Sender::new(settings)
+            .send(&sender, "127.0.0.1", &data)
+            .unwrap();
+
+        let first = receive(&receiver);
+        assert_eq!(universe(&first), 1);
+        assert_eq!(dmx_data(&first).len(), 512);
+        assert!(dmx_data(&first)[..100].iter().all(|&v| v == 0));
+        assert!(dmx_data(&first)[100..].iter().all(|&v| v == 7));
+        let second = receive(&receiver);
+        assert_eq!(universe(&second), 2);
+        assert_eq!(dmx_data(&second).len(), 600 - 412);
+    }
+
+    #[test]
+    fn multicast_groups_follow_the_universe() {
+        assert_eq!(multicast_address(1), Ipv4Addr::new(239, 255, 0, 1));
+        assert_eq!(multicast_address(63999), Ipv4Addr::new(239, 255, 249, 255));
+    }
+}
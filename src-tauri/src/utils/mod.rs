@@ -1,8 +1,16 @@
 // src-tauri/src/utils/mod.rs
 
+pub mod artnet;
 pub mod colors;
 pub mod ddp;
+pub mod delay;
+pub mod dmx;
 pub mod dsp;
+pub mod e131;
+pub mod opc;
+pub mod output_thread;
+pub mod serial;
+pub mod wled_udp;
 
 // Re-export the most used functions for convenience
 pub use colors::hsv_to_rgb;
@@ -0,0 +1,195 @@
+// src-tauri/src/utils/opc.rs
+
+use super::output_thread::OutputThread;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+pub const OPC_PORT: u16 = 7890;
+const CMD_SET_PIXEL_COLORS: u8 = 0;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+const WRITE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Target of an Open Pixel Control device (Fadecandy server, gl_server, ...).
+#[derive(Serialize, Deserialize, Type, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct OpcSettings {
+    /// OPC channel to address; 0 sends to every channel of the server.
+    pub channel: u8,
+    pub port: u16,
+}
+
+impl Default for OpcSettings {
+    fn default() -> Self {
+        Self {
+            channel: 0,
+            port: OPC_PORT,
+        }
+    }
+}
+
+/// Builds a "set pixel colors" message. Data beyond the 16-bit length is dropped.
+pub fn build_packet(channel: u8, data: &[u8]) -> Vec<u8> {
+    let len = data.len().min(u16::MAX as usize) / 3 * 3;
+    let mut packet = Vec::with_capacity(4 + len);
+    packet.push(channel);
+    packet.push(CMD_SET_PIXEL_COLORS);
+    packet.extend_from_slice(&(len as u16).to_be_bytes());
+    packet.extend_from_slice(&data[..len]);
+    packet
+}
+
+/// Streams frames to an OPC server over TCP, reconnecting when the connection drops.
+pub struct OpcSender {
+    channel: u8,
+    output: OutputThread,
+}
+
+impl OpcSender {
+    pub fn new(ip: &str, settings: &OpcSettings) -> Self {
+        let address = (ip.to_string(), settings.port);
+        let target = format!("OPC server {}:{}", ip, settings.port);
+        Self {
+            channel: settings.channel,
+            output: OutputThread::spawn("opc-output", target, move || connect(&address)),
+        }
+    }
+
+    pub fn send(&self, data: &[u8]) -> std::io::Result<()> {
+        self.output.send(build_packet(self.channel, data))
+    }
+}
+
+fn connect(address: &(String, u16)) -> std::io::Result<TcpStream> {
+    let mut last_error = None;
+    for socket_address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                stream.set_nodelay(true)?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "address did not resolve")
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Instant;
+
+    /// Minimal OPC server: accepts one client at a time and parses its messages.
+    struct MockServer {
+        listener: TcpListener,
+    }
+
+    impl MockServer {
+        fn start() -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.set_nonblocking(true).unwrap();
+            Self { listener }
+        }
+
+        fn port(&self) -> u16 {
+            self.listener.local_addr().unwrap().port()
+        }
+
+        /// Waits for a client while `sender` keeps sending `data`.
+        fn accept(&self, sender: &OpcSender, data: &[u8]) -> TcpStream {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            loop {
+                match self.listener.accept() {
+                    Ok((stream, _)) => {
+                        stream.set_nonblocking(false).unwrap();
+                        stream
+                            .set_read_timeout(Some(Duration::from_secs(2)))
+                            .unwrap();
+                        return stream;
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        assert!(Instant::now() < deadline, "client never connected");
+                        sender.send(data).unwrap();
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                    Err(e) => panic!("{}", e),
+                }
+            }
+        }
+
+        /// Reads one message as `(channel, command, data)`.
+        fn read_message(stream: &mut TcpStream) -> (u8, u8, Vec<u8>) {
+            let mut header = [0u8; 4];
+            stream.read_exact(&mut header).unwrap();
+            let len = u16::from_be_bytes([header[2], header[3]]) as usize;
+            let mut data = vec![0u8; len];
+            stream.read_exact(&mut data).unwrap();
+            (header[0], header[1], data)
+        }
+    }
+
+    #[test]
+    fn sends_set_pixel_colors_messages() {
+        let server = MockServer::start();
+        let settings = OpcSettings {
+            channel: 3,
+            port: server.port(),
+        };
+        let sender = OpcSender::new("127.0.0.1", &settings);
+        let data: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        let mut stream = server.accept(&sender, &data);
+
+        let (channel, command, received) = MockServer::read_message(&mut stream);
+        assert_eq!(channel, 3);
+        assert_eq!(command, CMD_SET_PIXEL_COLORS);
+        assert_eq!(received, data);
+    }
+
+    #[test]
+    fn reconnects_after_the_server_drops_the_connection() {
+        let server = MockServer::start();
+        let settings = OpcSettings {
+            port: server.port(),
+            ..Default::default()
+        };
+        let sender = OpcSender::new("127.0.0.1", &settings);
+        let mut first = server.accept(&sender, &[1, 1, 1]);
+        assert_eq!(MockServer::read_message(&mut first).2, vec![1, 1, 1]);
+        drop(first);
+
+        let mut second = server.accept(&sender, &[2, 2, 2]);
+        assert_eq!(MockServer::read_message(&mut second).2, vec![2, 2, 2]);
+    }
+
+    #[test]
+    fn keeps_retrying_until_the_server_starts() {
+        // Reserve a port, then close it so the first attempts are refused.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let settings = OpcSettings {
+            port,
+            ..Default::default()
+        };
+        let sender = OpcSender::new("127.0.0.1", &settings);
+        for _ in 0..5 {
+            sender.send(&[0, 0, 0]).unwrap();
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let server = MockServer { listener };
+        let mut stream = server.accept(&sender, &[5, 6, 7]);
+        assert_eq!(MockServer::read_message(&mut stream).2, vec![5, 6, 7]);
+    }
+}
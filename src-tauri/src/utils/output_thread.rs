@@ -0,0 +1,88 @@
+// src-tauri/src/utils/output_thread.rs
+
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MIN_RETRY_DELAY: Duration = Duration::from_millis(250);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Writes frames to a blocking connection (TCP stream, serial port, ...) on a
+/// background thread, so a slow or unreachable device never holds up the
+/// render loop. Frames the connection can't keep up with are dropped.
+///
+/// The connection is opened on the first frame and reopened with a growing
+/// delay whenever opening or writing fails. The thread ends when this handle
+/// is dropped.
+pub struct OutputThread {
+    frames: SyncSender<Vec<u8>>,
+}
+
+impl OutputThread {
+    /// `target` names the device in log messages.
+    pub fn spawn<W, F>(name: &str, target: String, open: F) -> Self
+    where
+        W: Write,
+        F: FnMut() -> io::Result<W> + Send + 'static,
+    {
+        let (frames, frame_rx) = mpsc::sync_channel(1);
+        let spawned = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || run(&target, open, frame_rx));
+        if let Err(e) = spawned {
+            eprintln!("[ENGINE] Failed to start output thread {}: {}", name, e);
+        }
+        Self { frames }
+    }
+
+    pub fn send(&self, frame: Vec<u8>) -> io::Result<()> {
+        match self.frames.try_send(frame) {
+            Ok(()) | Err(TrySendError::Full(_)) => Ok(()),
+            Err(TrySendError::Disconnected(_)) => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "output thread has stopped",
+            )),
+        }
+    }
+}
+
+fn run<W, F>(target: &str, mut open: F, frames: Receiver<Vec<u8>>)
+where
+    W: Write,
+    F: FnMut() -> io::Result<W>,
+{
+    let mut connection: Option<W> = None;
+    let mut next_attempt = Instant::now();
+    let mut retry_delay = MIN_RETRY_DELAY;
+    let mut failure_logged = false;
+
+    while let Ok(frame) = frames.recv() {
+        if connection.is_none() && Instant::now() >= next_attempt {
+            match open() {
+                Ok(opened) => {
+                    println!("[ENGINE] Connected to {}", target);
+                    connection = Some(opened);
+                    retry_delay = MIN_RETRY_DELAY;
+                    failure_logged = false;
+                }
+                Err(e) => {
+                    if !failure_logged {
+                        eprintln!("[ENGINE] Could not connect to {}: {}. Retrying.", target, e);
+                        failure_logged = true;
+                    }
+                    next_attempt = Instant::now() + retry_delay;
+                    retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+                }
+            }
+        }
+
+        if let Some(writer) = &mut connection {
+            if let Err(e) = writer.write_all(&frame).and_then(|_| writer.flush()) {
+                eprintln!("[ENGINE] Lost connection to {}: {}", target, e);
+                connection = None;
+                next_attempt = Instant::now();
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// src-tauri/src/utils/serial.rs
+
+use super::output_thread::OutputThread;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::time::Duration;
+
+const WRITE_TIMEOUT: Duration = Duration::from_millis(500);
+const TPM2_FRAME_START: u8 = 0xc9;
+const TPM2_DATA_FRAME: u8 = 0xda;
+const TPM2_FRAME_END: u8 = 0x36;
+
+/// How frames are delimited on the serial line.
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug, Default, PartialEq)]
+pub enum SerialFraming {
+    /// `Ada` + LED count + checksum header, as used by Arduino Adalight sketches.
+    #[default]
+    Adalight,
+    /// TPM2 data frames (0xC9 0xDA, size, data, 0x36).
+    Tpm2,
+}
+
+/// Serial port of a USB/serial LED controller.
+#[derive(Serialize, Deserialize, Type, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SerialSettings {
+    /// e.g. `/dev/ttyUSB0` or `COM3`.
+    pub path: String,
+    pub baud_rate: u32,
+    pub framing: SerialFraming,
+}
+
+impl Default for SerialSettings {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            baud_rate: 115200,
+            framing: SerialFraming::default(),
+        }
+    }
+}
+
+/// Wraps one frame of RGB pixel data in the given framing.
+pub fn build_frame(framing: SerialFraming, data: &[u8]) -> Vec<u8> {
+    match framing {
+        SerialFraming::Adalight => {
+            let len = data.len().min(65536 * 3) / 3 * 3;
+            let [hi, lo] = ((len / 3).saturating_sub(1) as u16).to_be_bytes();
+            let mut frame = vec![b'A', b'd', b'a', hi, lo, hi ^ lo ^ 0x55];
+            frame.extend_from_slice(&data[..len]);
+            frame
+        }
+        SerialFraming::Tpm2 => {
+            let len = data.len().min(u16::MAX as usize) / 3 * 3;
+            let mut frame = vec![TPM2_FRAME_START, TPM2_DATA_FRAME];
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+            frame.extend_from_slice(&data[..len]);
+            frame.push(TPM2_FRAME_END);
+            frame
+        }
+    }
+}
+
+/// Streams frames to a serial port, reopening it when it goes away (e.g. a
+/// replugged Arduino).
+pub struct SerialSender {
+    framing: SerialFraming,
+    output: OutputThread,
+}
+
+impl SerialSender {
+    pub fn new(settings: &SerialSettings) -> Self {
+        let path = settings.path.clone();
+        let baud_rate = settings.baud_rate;
+        let target = format!("serial port {} ({} baud)", path, baud_rate);
+        Self {
+            framing: settings.framing,
+            output: OutputThread::spawn("serial-output", target, move || {
+                serialport::new(&path, baud_rate)
+                    .timeout(WRITE_TIMEOUT)
+                    .open()
+                    .map_err(std::io::Error::from)
+            }),
+        }
+    }
+
+    pub fn send(&self, data: &[u8]) -> std::io::Result<()> {
+        // An Adalight header can't say "no LEDs"; it would announce one.
+        if data.len() < 3 {
+            return Ok(());
+        }
+        self.output.send(build_frame(self.framing, data))
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use serialport::{SerialPort, TTYPort};
+    use std::io::Read;
+
+    /// Sends one frame to the slave side of a pseudo-terminal pair and reads it
+    /// back from the master side.
+    fn send_through_pty(framing: SerialFraming, data: &[u8], expected_len: usize) -> Vec<u8> {
+        let (mut master, slave) = TTYPort::pair().unwrap();
+        master.set_timeout(Duration::from_secs(2)).unwrap();
+        let settings = SerialSettings {
+            path: slave.name().unwrap(),
+            framing,
+            ..Default::default()
+        };
+        let sender = SerialSender::new(&settings);
+        sender.send(data).unwrap();
+
+        let mut received = vec![0u8; expected_len];
+        master.read_exact(&mut received).unwrap();
+        received
+    }
+
+    #[test]
+    fn writes_adalight_frames() {
+        let data: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        let received = send_through_pty(SerialFraming::Adalight, &data, 6 + 300);
+        // 100 LEDs are announced as count - 1 = 99.
+        assert_eq!(&received[..6], &[b'A', b'd', b'a', 0, 99, 99 ^ 0x55]);
+        assert_eq!(&received[6..], &data[..]);
+    }
+
+    #[test]
+    fn writes_tpm2_frames() {
+        let data: Vec<u8> = (0..300).map(|i| (i * 7) as u8).collect();
+        let received = send_through_pty(SerialFraming::Tpm2, &data, 4 + 300 + 1);
+        assert_eq!(&received[..4], &[0xc9, 0xda, 0x01, 0x2c]);
+        assert_eq!(&received[4..304], &data[..]);
+        assert_eq!(received[304], 0x36);
+    }
+}
@@ -0,0 +1,219 @@
+// src-tauri/src/utils/wled_udp.rs
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::net::UdpSocket;
+
+/// WLED's default `udpport`. Settings built from a discovered `wled::WledDevice`
+/// use the port it reports instead.
+pub const WLED_UDP_PORT: u16 = 21324;
+const WARLS_MAX_LEDS: usize = 255;
+const DRGB_MAX_LEDS: usize = 490;
+const DRGBW_MAX_LEDS: usize = 367;
+const DNRGB_MAX_LEDS: usize = 489;
+
+/// Packet formats of WLED's UDP realtime interface.
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug, Default, PartialEq)]
+pub enum WledUdpProtocol {
+    /// Index + RGB per LED; only the first 255 LEDs can be addressed.
+    Warls,
+    /// Plain RGB from the first LED, up to 490 LEDs.
+    Drgb,
+    /// RGBW from the first LED, up to 367 LEDs. The white channel is sent as 0.
+    Drgbw,
+    /// RGB with a start index, split into packets of 489 LEDs for long strips.
+    #[default]
+    Dnrgb,
+}
+
+impl WledUdpProtocol {
+    fn id(self) -> u8 {
+        match self {
+            WledUdpProtocol::Warls => 1,
+            WledUdpProtocol::Drgb => 2,
+            WledUdpProtocol::Drgbw => 3,
+            WledUdpProtocol::Dnrgb => 4,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Type, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct WledUdpSettings {
+    pub protocol: WledUdpProtocol,
+    /// Seconds without packets after which WLED goes back to its own effects.
+    /// 255 keeps it in realtime mode until it is told otherwise.
+    pub timeout_secs: u8,
+    pub port: u16,
+}
+
+impl Default for WledUdpSettings {
+    fn default() -> Self {
+        Self {
+            protocol: WledUdpProtocol::default(),
+            timeout_secs: 2,
+            port: WLED_UDP_PORT,
+        }
+    }
+}
+
+/// Builds the packets for one frame of RGB pixel data.
+/// Pixels beyond what the protocol can address are dropped.
+pub fn build_packets(settings: &WledUdpSettings, data: &[u8]) -> Vec<Vec<u8>> {
+    let header = [settings.protocol.id(), settings.timeout_secs];
+    let pixels = data.chunks_exact(3);
+    match settings.protocol {
+        WledUdpProtocol::Warls => {
+            let mut packet = header.to_vec();
+            for (index, pixel) in pixels.take(WARLS_MAX_LEDS).enumerate() {
+                packet.push(index as u8);
+                packet.extend_from_slice(pixel);
+            }
+            vec![packet]
+        }
+        WledUdpProtocol::Drgb => {
+            let len = data.len().min(DRGB_MAX_LEDS * 3) / 3 * 3;
+            vec![[&header[..], &data[..len]].concat()]
+        }
+        WledUdpProtocol::Drgbw => {
+            let mut packet = header.to_vec();
+            for pixel in pixels.take(DRGBW_MAX_LEDS) {
+                packet.extend_from_slice(pixel);
+                packet.push(0);
+            }
+            vec![packet]
+        }
+        WledUdpProtocol::Dnrgb => data
+            .chunks(DNRGB_MAX_LEDS * 3)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let start_index = (i * DNRGB_MAX_LEDS) as u16;
+                let len = chunk.len() / 3 * 3;
+                [&header[..], &start_index.to_be_bytes(), &chunk[..len]].concat()
+            })
+            .collect(),
+    }
+}
+
+pub fn send_wled_udp_packets(
+    socket: &UdpSocket,
+    ip: &str,
+    settings: &WledUdpSettings,
+    data: &[u8],
+) -> Result<(), std::io::Error> {
+    for packet in build_packets(settings, data) {
+        socket.send_to(&packet, (ip, settings.port))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn loopback_pair() -> (UdpSocket, UdpSocket) {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        (sender, receiver)
+    }
+
+    fn receive(receiver: &UdpSocket) -> Vec<u8> {
+        let mut buf = [0u8; 1500];
+        let len = receiver.recv(&mut buf).unwrap();
+        buf[..len].to_vec()
+    }
+
+    fn settings(receiver: &UdpSocket, protocol: WledUdpProtocol) -> WledUdpSettings {
+        WledUdpSettings {
+            protocol,
+            port: receiver.local_addr().unwrap().port(),
+            ..Default::default()
+        }
+    }
+
+    fn pixels(count: usize) -> Vec<u8> {
+        (0..count * 3).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn dnrgb_splits_long_strips_with_advancing_start_index() {
+        let (sender, receiver) = loopback_pair();
+        let settings = settings(&receiver, WledUdpProtocol::Dnrgb);
+        let data = pixels(1000);
+        send_wled_udp_packets(&sender, "127.0.0.1", &settings, &data).unwrap();
+
+        for (start, end) in [(0, 489), (489, 978), (978, 1000)] {
+            let packet = receive(&receiver);
+            assert_eq!(packet[0], 4);
+            assert_eq!(u16::from_be_bytes([packet[2], packet[3]]), start as u16);
+            assert_eq!(&packet[4..], &data[start * 3..end * 3]);
+        }
+    }
+
+    #[test]
+    fn warls_prefixes_every_pixel_with_its_index() {
+        let (sender, receiver) = loopback_pair();
+        let settings = settings(&receiver, WledUdpProtocol::Warls);
+        let data = pixels(300);
+        send_wled_udp_packets(&sender, "127.0.0.1", &settings, &data).unwrap();
+
+        let packet = receive(&receiver);
+        assert_eq!(packet[0], 1);
+        // Only the first 255 LEDs can be addressed with one index byte.
+        assert_eq!(packet.len(), 2 + 255 * 4);
+        for (index, led) in packet[2..].chunks(4).enumerate() {
+            assert_eq!(led[0], index as u8);
+            assert_eq!(led[1..], data[index * 3..index * 3 + 3]);
+        }
+    }
+
+    #[test]
+    fn drgbw_appends_an_empty_white_channel() {
+        let (sender, receiver) = loopback_pair();
+        let settings = settings(&receiver, WledUdpProtocol::Drgbw);
+        send_wled_udp_packets(&sender, "127.0.0.1", &settings, &[1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(receive(&receiver), [3, 2, 1, 2, 3, 0, 4, 5, 6, 0]);
+
+        send_wled_udp_packets(&sender, "127.0.0.1", &settings, &pixels(400)).unwrap();
+        assert_eq!(receive(&receiver).len(), 2 + DRGBW_MAX_LEDS * 4);
+    }
+
+    #[test]
+    fn drgb_drops_pixels_it_cannot_address() {
+        let (sender, receiver) = loopback_pair();
+        let settings = settings(&receiver, WledUdpProtocol::Drgb);
+        let data = pixels(500);
+        send_wled_udp_packets(&sender, "127.0.0.1", &settings, &data).unwrap();
+
+        let packet = receive(&receiver);
+        assert_eq!(packet[0], 2);
+        assert_eq!(&packet[2..], &data[..DRGB_MAX_LEDS * 3]);
+
+        // A trailing partial pixel isn't sent.
+        send_wled_udp_packets(&sender, "127.0.0.1", &settings, &[1, 2, 3, 4]).unwrap();
+        assert_eq!(receive(&receiver), [2, 2, 1, 2, 3]);
+    }
+
+    #[test]
+    fn every_packet_carries_the_timeout() {
+        let (sender, receiver) = loopback_pair();
+        for protocol in [
+            WledUdpProtocol::Warls,
+            WledUdpProtocol::Drgb,
+            WledUdpProtocol::Drgbw,
+            WledUdpProtocol::Dnrgb,
+        ] {
+            let settings = WledUdpSettings {
+                timeout_secs: 255,
+                ..settings(&receiver, protocol)
+            };
+            send_wled_udp_packets(&sender, "127.0.0.1", &settings, &pixels(10)).unwrap();
+            let packet = receive(&receiver);
+            assert_eq!(packet[..2], [protocol.id(), 255]);
+        }
+    }
+}
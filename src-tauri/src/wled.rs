@@ -1,5 +1,6 @@
 // src-tauri/src/wled.rs
 
+use crate::utils::wled_udp::WledUdpSettings;
 use mdns_sd::{ServiceDaemon, ServiceEvent};
 use serde::{Deserialize, Serialize};
 use specta::Type;
@@ -38,6 +39,16 @@ pub struct WledDevice {
     pub maps: Vec<MapInfo>,
 }
 
+impl From<&WledDevice> for WledUdpSettings {
+    /// Realtime output aimed at the `udpport` the device reported.
+    fn from(device: &WledDevice) -> Self {
+        Self {
+            port: device.udp_port,
+            ..Default::default()
+        }
+    }
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn discover_wled(
@@ -334,7 +334,7 @@ const DspSettings = () => {
 				<Slider
 					value={dirtyDspSettings.audio_delay_ms}
 					onChange={(_e, val) => handleSettingChange('audio_delay_ms', val as number)}
-					min={0}
+					min={-500}
 					max={500}
 					step={10}
 				/>